@@ -0,0 +1,83 @@
+use std::fs;
+
+#[derive(Debug)]
+pub(crate) struct CpuQuotaSource(CpuQuotaSourceInner);
+
+#[derive(Debug)]
+enum CpuQuotaSourceInner {
+    Production,
+    #[cfg(test)]
+    Fake {
+        cores: Option<f32>,
+    },
+}
+
+impl CpuQuotaSource {
+    pub(crate) fn new() -> CpuQuotaSource {
+        CpuQuotaSource(CpuQuotaSourceInner::Production)
+    }
+
+    /// Returns the number of cpu cores the current cgroup is allowed to use, or `None` if the
+    /// cgroup isn't limited or the quota can't be read.
+    pub(crate) fn cores(&self) -> Option<f32> {
+        match self {
+            CpuQuotaSource(CpuQuotaSourceInner::Production) => {
+                read_cgroup_v2_quota().or_else(read_cgroup_v1_quota)
+            }
+            #[cfg(test)]
+            CpuQuotaSource(CpuQuotaSourceInner::Fake { cores }) => *cores,
+        }
+    }
+}
+
+fn read_cgroup_v2_quota() -> Option<f32> {
+    parse_cpu_max(&fs::read_to_string("/sys/fs/cgroup/cpu.max").ok()?)
+}
+
+fn read_cgroup_v1_quota() -> Option<f32> {
+    let quota = fs::read_to_string("/sys/fs/cgroup/cpu/cpu.cfs_quota_us").ok()?;
+    let period = fs::read_to_string("/sys/fs/cgroup/cpu/cpu.cfs_period_us").ok()?;
+    quota_in_cores(quota.trim(), period.trim())
+}
+
+fn parse_cpu_max(contents: &str) -> Option<f32> {
+    let mut words = contents.split_whitespace();
+    quota_in_cores(words.next()?, words.next()?)
+}
+
+fn quota_in_cores(quota: &str, period: &str) -> Option<f32> {
+    let quota: f32 = quota.parse().ok()?;
+    let period: f32 = period.parse().ok()?;
+    if quota > 0.0 && period > 0.0 {
+        Some(quota / period)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    impl CpuQuotaSource {
+        pub(crate) fn fake(cores: Option<f32>) -> CpuQuotaSource {
+            CpuQuotaSource(CpuQuotaSourceInner::Fake { cores })
+        }
+    }
+
+    #[test]
+    fn parses_cgroup_v2_quotas() {
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cpu_max("50000 100000\n"), Some(0.5));
+    }
+
+    #[test]
+    fn unlimited_cgroup_v2_quotas_are_ignored() {
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+    }
+
+    #[test]
+    fn unlimited_cgroup_v1_quotas_are_ignored() {
+        assert_eq!(quota_in_cores("-1", "100000"), None);
+    }
+}
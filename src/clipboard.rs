@@ -0,0 +1,73 @@
+use crate::R;
+#[cfg(test)]
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+#[cfg(test)]
+use std::rc::Rc;
+
+/// The system clipboard. Only available when built with the 'clipboard' feature.
+pub(crate) struct Clipboard(ClipboardInner);
+
+enum ClipboardInner {
+    /// Created on first use and then kept, since on X11 the copied text is only available while
+    /// the clipboard object is alive.
+    #[cfg(feature = "clipboard")]
+    System(Option<arboard::Clipboard>),
+    #[cfg(not(feature = "clipboard"))]
+    Unsupported,
+    #[cfg(test)]
+    Fake(Rc<RefCell<Option<String>>>),
+}
+
+impl Debug for Clipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clipboard")
+    }
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Clipboard {
+        #[cfg(feature = "clipboard")]
+        return Clipboard(ClipboardInner::System(None));
+        #[cfg(not(feature = "clipboard"))]
+        return Clipboard(ClipboardInner::Unsupported);
+    }
+
+    #[cfg_attr(not(any(feature = "clipboard", test)), allow(unused_variables))]
+    pub(crate) fn copy(&mut self, text: &str) -> R<()> {
+        match self {
+            #[cfg(feature = "clipboard")]
+            Clipboard(ClipboardInner::System(clipboard)) => {
+                let clipboard = match clipboard {
+                    Some(clipboard) => clipboard,
+                    None => clipboard.insert(arboard::Clipboard::new()?),
+                };
+                clipboard.set_text(text)?;
+            }
+            #[cfg(not(feature = "clipboard"))]
+            Clipboard(ClipboardInner::Unsupported) => {
+                Err("treetop was built without the 'clipboard' feature")?;
+            }
+            #[cfg(test)]
+            Clipboard(ClipboardInner::Fake(contents)) => {
+                *contents.borrow_mut() = Some(text.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    impl Clipboard {
+        pub(crate) fn fake() -> (Clipboard, Rc<RefCell<Option<String>>>) {
+            let contents = Rc::new(RefCell::new(None));
+            (
+                Clipboard(ClipboardInner::Fake(Rc::clone(&contents))),
+                contents,
+            )
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+/// Delays an action until nothing triggered it for a while, e.g. until the user stops typing.
+#[derive(Debug)]
+pub(crate) struct Debouncer {
+    delay: Duration,
+    last_trigger: Option<Instant>,
+}
+
+impl Debouncer {
+    pub(crate) fn new(delay: Duration) -> Self {
+        Debouncer {
+            delay,
+            last_trigger: None,
+        }
+    }
+
+    /// Schedules the action, pushing it back if it was already scheduled.
+    pub(crate) fn trigger(&mut self, now: Instant) {
+        self.last_trigger = Some(now);
+    }
+
+    /// How long to wait until the action is due. `None` if nothing is scheduled.
+    pub(crate) fn remaining(&self, now: Instant) -> Option<Duration> {
+        let last_trigger = self.last_trigger?;
+        Some((last_trigger + self.delay).saturating_duration_since(now))
+    }
+
+    /// Returns whether the action is due. If so, it's unscheduled.
+    pub(crate) fn take_due(&mut self, now: Instant) -> bool {
+        if self.remaining(now) == Some(Duration::ZERO) {
+            self.last_trigger = None;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(150);
+
+    #[test]
+    fn nothing_is_due_without_triggers() {
+        let mut debouncer = Debouncer::new(DELAY);
+        let now = Instant::now();
+        assert_eq!(debouncer.remaining(now), None);
+        assert!(!debouncer.take_due(now + DELAY));
+    }
+
+    #[test]
+    fn is_due_after_the_delay() {
+        let mut debouncer = Debouncer::new(DELAY);
+        let start = Instant::now();
+        debouncer.trigger(start);
+        assert_eq!(
+            debouncer.remaining(start + Duration::from_millis(100)),
+            Some(Duration::from_millis(50))
+        );
+        assert!(!debouncer.take_due(start + Duration::from_millis(100)));
+        assert!(debouncer.take_due(start + DELAY));
+    }
+
+    #[test]
+    fn triggering_again_pushes_the_action_back() {
+        let mut debouncer = Debouncer::new(DELAY);
+        let start = Instant::now();
+        debouncer.trigger(start);
+        debouncer.trigger(start + Duration::from_millis(100));
+        assert!(!debouncer.take_due(start + DELAY));
+        assert!(debouncer.take_due(start + Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn is_only_due_once_per_trigger() {
+        let mut debouncer = Debouncer::new(DELAY);
+        let start = Instant::now();
+        debouncer.trigger(start);
+        assert!(debouncer.take_due(start + DELAY));
+        assert!(!debouncer.take_due(start + DELAY * 2));
+        assert_eq!(debouncer.remaining(start + DELAY * 2), None);
+    }
+}
@@ -0,0 +1,112 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Clear, Paragraph, Widget},
+};
+
+/// All keybindings, grouped by the mode they apply in. `TreetopApp::update` is the
+/// authoritative implementation, so this table has to be changed together with it. A test
+/// checks that every key listed here is handled.
+pub(crate) const KEYBINDINGS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Process list",
+        &[
+            ("q, Ctrl+C", "quit (also Esc with --esc-quits)"),
+            ("?", "show this help"),
+            ("↑↓, k j", "move the selection"),
+            ("PgUp PgDn", "move the selection by 20 rows"),
+            ("g, G", "jump to the first / last row"),
+            ("← →", "scroll the selected command horizontally"),
+            (
+                "Alt+← Alt+→",
+                "go back / forward to previously selected processes",
+            ),
+            ("Enter", "select the highlighted process"),
+            ("/", "edit the search pattern"),
+            (":", "jump to a pid"),
+            (
+                "Tab, r",
+                "next sort column (Shift+Tab: previous), reverse the sort order",
+            ),
+            ("z", "toggle sorting roots by number of descendants"),
+            ("m, M", "set the minimum cpu / ram usage"),
+            ("a", "toggle aggregating the usage of descendants"),
+            ("o", "toggle the number of descendants"),
+            (
+                "e, c",
+                "collapse / expand the selected subtree, all subtrees",
+            ),
+            ("+ -", "show more / fewer tree levels"),
+            ("> <", "zoom into the selected subtree, zoom back out"),
+            ("f", "toggle listing matches without their ancestors"),
+            (
+                "v",
+                "toggle a flat list of all processes, with their own usage",
+            ),
+            ("l", "toggle showing only processes without children"),
+            (
+                "h",
+                "toggle highlighting matches without hiding other processes",
+            ),
+            ("p", "toggle full executable paths"),
+            ("H", "toggle showing threads"),
+            ("y", "copy the selected command to the clipboard"),
+            ("T", "send SIGTERM to all processes matching the pattern"),
+            ("Space, F5", "pause updates, refresh now"),
+        ],
+    ),
+    (
+        "Search pattern",
+        &[
+            ("Enter, Esc", "stop editing the pattern"),
+            ("Backspace", "delete the last character"),
+            ("Ctrl+F", "toggle literal matching"),
+            ("Ctrl+W", "toggle matching whole words"),
+            ("Ctrl+Z", "toggle fuzzy matching, e.g. 'frfx' for 'firefox'"),
+        ],
+    ),
+    (
+        "Selected process",
+        &[
+            (
+                "t K h i s c",
+                "SIGTERM, SIGKILL (also Ctrl+K), SIGHUP, SIGINT, SIGSTOP, SIGCONT",
+            ),
+            ("x, X", "send SIGTERM / SIGKILL to the whole subtree"),
+            ("y", "copy the command to the clipboard"),
+            ("Esc", "unselect"),
+        ],
+    ),
+    (
+        "Prompts",
+        &[
+            ("Enter, Esc", "apply, cancel"),
+            ("y", "confirm sending a signal, any other key cancels"),
+        ],
+    ),
+];
+
+/// Renders the keybindings over the whole `area`.
+pub(crate) fn render(area: Rect, buffer: &mut Buffer) {
+    let key_width = KEYBINDINGS
+        .iter()
+        .flat_map(|(_, bindings)| bindings.iter())
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![Line::from("Keybindings (press any key to close)".bold())];
+    for (mode, bindings) in KEYBINDINGS {
+        lines.push(Line::default());
+        lines.push(Line::from(mode.yellow().bold()));
+        for (keys, description) in *bindings {
+            lines.push(Line::from(vec![
+                format!("  {keys:<key_width$}  ").blue(),
+                (*description).into(),
+            ]));
+        }
+    }
+    Clear.render(area, buffer);
+    Paragraph::new(lines).render(area, buffer);
+}
@@ -0,0 +1,90 @@
+use crate::regex::Regex;
+use ratatui::style::Style;
+use ratatui::text::Span;
+
+/// Splits `text` into spans styled with `base`, patched with the given highlight style wherever
+/// the corresponding regex matches. Later highlights are applied after (and on top of) earlier
+/// ones.
+pub(crate) fn style_spans(
+    text: &str,
+    base: Style,
+    highlights: &[(&Regex, Style)],
+) -> Vec<Span<'static>> {
+    let mut styles = vec![base; text.len()];
+    for (regex, highlight) in highlights {
+        for range in regex.find_ranges(text) {
+            for style in &mut styles[range] {
+                *style = style.patch(*highlight);
+            }
+        }
+    }
+    let mut result = Vec::new();
+    let mut start = 0;
+    for end in 1..=text.len() {
+        if end == text.len() || (text.is_char_boundary(end) && styles[end] != styles[start]) {
+            result.push(Span::styled(text[start..end].to_string(), styles[start]));
+            start = end;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::R;
+    use ratatui::style::Stylize;
+
+    #[test]
+    fn leaves_text_without_matches_in_one_span() -> R<()> {
+        assert_eq!(
+            style_spans(
+                "foo bar",
+                Style::new(),
+                &[(&Regex::new("baz")?, Style::new().red())]
+            ),
+            vec![Span::raw("foo bar")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_matches_of_multiple_patterns() -> R<()> {
+        assert_eq!(
+            style_spans(
+                "error retry",
+                Style::new(),
+                &[
+                    (&Regex::new("error")?, Style::new().red()),
+                    (&Regex::new("retry")?, Style::new().yellow()),
+                ]
+            ),
+            vec![
+                Span::styled("error", Style::new().red()),
+                Span::raw(" "),
+                Span::styled("retry", Style::new().yellow()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn later_highlights_are_applied_on_top() -> R<()> {
+        assert_eq!(
+            style_spans(
+                "abc",
+                Style::new(),
+                &[
+                    (&Regex::new("abc")?, Style::new().red()),
+                    (&Regex::new("b")?, Style::new().yellow()),
+                ]
+            ),
+            vec![
+                Span::styled("a", Style::new().red()),
+                Span::styled("b", Style::new().yellow()),
+                Span::styled("c", Style::new().red()),
+            ]
+        );
+        Ok(())
+    }
+}
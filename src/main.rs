@@ -1,45 +1,445 @@
+use crate::proc_dir::ProcDir;
+use crate::process::ArgumentSeparator;
+use crate::process::Column;
 use crate::process::ProcessWatcher;
+use crate::process::SortBy;
+use crate::signaler::KillSignaler;
+use crate::state::StateFile;
+use crate::theme::Theme;
+use crate::tree::TreeStyle;
 use crate::treetop_app::TreetopApp;
 use clap::{CommandFactory, Parser};
 use std::error::Error;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
 use sysinfo::System;
 
+mod cgroup;
+mod clipboard;
+mod debounce;
+mod help;
+mod highlight;
+mod navigation_history;
+mod proc_dir;
 mod process;
 mod regex;
+mod signaler;
+mod state;
+mod theme;
 mod tree;
 mod treetop_app;
 mod tui_app;
+mod usage_log;
 mod utils;
 
 type R<A> = Result<A, Box<dyn Error>>;
 
 #[derive(Parser, Debug)]
-#[cfg_attr(test, derive(Default))]
 #[command(disable_help_flag = true)]
+#[allow(clippy::struct_excessive_bools)]
 struct Args {
     #[arg(short = 'h', long = "help")]
     /// Print this help
     help: bool,
 
     /// Search pattern for filtering the process tree
+    ///
+    /// Comma-separated terms (e.g. 'nginx,postgres') show processes matching any of them.
+    /// 'parent:' followed by a pattern (e.g. 'parent:sshd') shows the children of the processes
+    /// with matching names. 'user:' followed by a pattern (e.g. 'user:www') shows the processes
+    /// owned by matching users. 'pid:1000-2000' and 'pid:1234' show the processes with pids in
+    /// the given (inclusive) range. These can also be used as terms, e.g. 'nginx,user:www'.
     pattern: Option<String>,
 
+    #[arg(long, value_name = "PATTERN")]
+    /// Highlight matches of a second pattern (in magenta) without filtering by it
+    highlight: Option<String>,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// How process arguments are joined before matching them against the search pattern
+    ///
+    /// With 'space' (the default) patterns can span multiple arguments just like they appear on
+    /// a command line, but can't tell a space inside an argument from one between arguments.
+    /// 'none' allows matching on arguments that were split up (e.g. '-ofile'), but may produce
+    /// matches across argument boundaries that look surprising. 'newline' allows anchoring
+    /// patterns to single arguments with '(?m)^' and '$'.
+    argument_separator: ArgumentSeparator,
+
+    #[arg(long)]
+    /// Send signals to the selected process without asking for confirmation
+    no_confirm: bool,
+
+    #[arg(long)]
+    /// Only match whole words, e.g. 'ss' matches 'ss -tlnp' but not 'assist'
+    ///
+    /// This can also be toggled at runtime with Ctrl+W.
+    word: bool,
+
+    #[arg(long)]
+    /// Match the characters of the search pattern in order, but not necessarily next to each
+    /// other, e.g. 'frfx' matches 'firefox'
+    ///
+    /// This replaces regex, literal and whole word matching. It can also be toggled at runtime
+    /// with Ctrl+Z.
+    fuzzy: bool,
+
+    #[arg(long)]
+    /// Also match the search pattern against the user owning a process
+    match_user: bool,
+
+    #[arg(long)]
+    /// Also match the search pattern against the working directory of a process
+    search_cwd: bool,
+
     #[arg(long)]
     /// Prevents treetop from hiding itself
     ///
     /// By default treetop will hide itself (i.e. its own process) if and only if matched on
     /// process arguments. Otherwise treetop would often show itself when passing a search pattern
     /// as an argument. This is usually not useful. This flag makes sure treetop always shows
-    /// itself when matched.
+    /// itself when matched. Setting the environment variable `TREETOP_DONT_HIDE_SELF` to anything
+    /// but '', '0' or 'false' has the same effect.
     dont_hide_self: bool,
+
+    #[arg(long)]
+    /// Show cpu usage relative to the cpu quota of the current cgroup
+    ///
+    /// Inside containers with a cpu limit this makes 100% mean "using the whole quota" instead
+    /// of "using one core of the host". Without a readable quota cpu usage is shown as usual.
+    container_cpu: bool,
+
+    #[arg(long, value_name = "CPU", default_value_t = 1.0)]
+    /// Cpu threshold (in percent) used when collapsing idle subtrees with 'c'
+    ///
+    /// Pressing 'c' collapses all subtrees whose accumulated cpu usage is below this threshold,
+    /// leaving only the busy branches expanded. Pressing 'c' again expands everything.
+    collapse_below: f32,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "COLUMNS",
+        value_delimiter = ',',
+        default_values_t = SortBy::all()
+    )]
+    /// Comma-separated list of columns that TAB cycles through when sorting
+    ///
+    /// Columns that are left out are never used for sorting and are dimmed in the header.
+    sort_columns: Vec<SortBy>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "COLUMNS",
+        value_delimiter = ',',
+        default_values_t = Column::all()
+    )]
+    /// Comma-separated list of columns to show left of the tree, in this order
+    ///
+    /// The name column is always shown. Hidden columns are skipped by TAB, but can still be sorted
+    /// by with '--sort'.
+    columns: Vec<Column>,
+
+    #[arg(long, value_enum, value_name = "COLUMN")]
+    /// Column to sort by on startup
+    ///
+    /// Name and pid are sorted in ascending order, all other columns in descending order.
+    sort: Option<SortBy>,
+
+    #[arg(long)]
+    /// Round cpu usages to whole percents when sorting by cpu
+    ///
+    /// This keeps processes with similar usage from swapping places on every refresh.
+    stable: bool,
+
+    #[arg(long)]
+    /// Order root processes by their number of descendants (largest first)
+    ///
+    /// Children are still sorted by the selected sort column. This can also be toggled at
+    /// runtime with 'z'.
+    sort_roots_by_size: bool,
+
+    #[arg(long)]
+    /// Show the cpu and ram usage of every process on its own, instead of including the usage of
+    /// all its descendants
+    ///
+    /// This can also be toggled at runtime with 'a'.
+    no_aggregate: bool,
+
+    #[arg(long)]
+    /// Show the processes matching the search pattern as a flat list, without their ancestors
+    ///
+    /// This can also be toggled at runtime with 'f'.
+    flat: bool,
+
+    #[arg(long)]
+    /// Show userland threads below their processes
+    ///
+    /// Threads are shown with their own names, and their usage isn't added to their process,
+    /// which already includes it. This can also be toggled at runtime with 'H'.
+    threads: bool,
+
+    #[arg(long)]
+    /// Show all kernel threads directly below a single '[kernel]' node
+    group_kernel: bool,
+
+    #[arg(long)]
+    /// Only show root processes, with the cpu and ram usage of their whole subtrees
+    root_only: bool,
+
+    #[arg(long, value_name = "N")]
+    /// Only show the first N levels of the process tree
+    ///
+    /// Deeper processes are collapsed into their visible ancestors, whose cpu and ram usage still
+    /// include them. This can also be adjusted at runtime with '+' and '-'.
+    max_depth: Option<usize>,
+
+    #[arg(long)]
+    /// Show the number of descendants next to every process
+    ///
+    /// This can also be toggled at runtime with 'o'. Single subtrees can be collapsed and
+    /// expanded with 'e'.
+    outline: bool,
+
+    #[arg(long)]
+    /// Show the full path of executables instead of just their file names
+    ///
+    /// This can also be toggled at runtime with 'p'.
+    full_path: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// Which characters to use for drawing the tree and the table
+    tree_style: TreeStyle,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// The colors to use for the table, the tree and search matches
+    theme: Theme,
+
+    #[arg(long)]
+    /// Print the process tree once to stdout and exit, instead of starting the interactive UI
+    ///
+    /// Exits with 1 if a search pattern was given and no process matched it, with 2 on errors,
+    /// and with 0 otherwise.
+    print: bool,
+
+    #[arg(long)]
+    /// Sample cpu usage once before showing anything
+    ///
+    /// Cpu usage is computed from the difference between two samples, so without this all
+    /// processes show 0% cpu until the first refresh. Delays the start by a fraction of a second.
+    warmup: bool,
+
+    #[arg(long, value_name = "N")]
+    /// Print the N processes using the most cpu to stdout as a flat list and exit
+    ///
+    /// Only processes matching the search pattern are listed, and their usage doesn't include
+    /// their descendants. Implies '--warmup', since cpu usages need two samples. Uses the same
+    /// exit codes as '--print'.
+    top: Option<usize>,
+
+    #[arg(long, value_name = "NAME")]
+    /// Hide processes with the given name, e.g. '--hide bash --hide zsh'
+    ///
+    /// Hidden processes are still shown when they're ancestors of a visible process. Can be
+    /// given multiple times.
+    hide: Vec<String>,
+
+    #[arg(long, value_name = "NAME")]
+    /// Only show processes owned by the user with the given name
+    ///
+    /// Their ancestors are still shown to keep the tree intact, unless '--user-only' is given.
+    /// Combines with the search pattern.
+    user: Option<String>,
+
+    #[arg(long, requires = "user")]
+    /// With '--user', also hide the ancestors owned by other users
+    ///
+    /// The remaining processes move up in the tree in their place.
+    user_only: bool,
+
+    #[arg(long)]
+    /// Don't use colors or other text styles, e.g. for terminals that don't support them
+    ///
+    /// The selected process is marked with a '*' instead of being shown in reverse video. Also
+    /// enabled by setting the environment variable `NO_COLOR`.
+    no_color: bool,
+
+    #[arg(long)]
+    /// Don't capture the mouse, so that text can be selected with it as usual
+    ///
+    /// Processes can't be selected by clicking them then.
+    no_mouse: bool,
+
+    #[arg(long, value_name = "PID")]
+    /// Start with the process with the given pid selected, ready to send it signals
+    ///
+    /// If the process isn't shown (e.g. because it doesn't exist or doesn't match the search
+    /// pattern), treetop starts normally and shows an error.
+    select: Option<u32>,
+
+    #[arg(long)]
+    /// Print the process tree once to stdout as json and exit
+    ///
+    /// Uses the same exit codes as '--print'.
+    json: bool,
+
+    #[arg(long)]
+    /// Quit with Esc when no search, selection or prompt is active
+    ///
+    /// Esc still only cancels these when they are.
+    esc_quits: bool,
+
+    #[arg(long, value_name = "MS", default_value_t = 1000)]
+    /// Refresh interval in milliseconds (at least 100)
+    interval: u64,
+
+    #[arg(long, value_name = "PATH")]
+    /// Read processes from a captured copy of /proc instead of the live system
+    ///
+    /// Only the 'stat' and 'cmdline' files of every process and the top-level 'meminfo' file are
+    /// used. Since a single snapshot doesn't allow computing cpu usage, it is always shown as 0%.
+    proc_dir: Option<PathBuf>,
+
+    #[arg(long)]
+    /// Don't restore the search pattern, sort column and collapsed subtrees of the last run,
+    /// and don't save them on exit
+    ///
+    /// By default they are saved to 'treetop/state.json' in your config directory (usually
+    /// '~/.config'). A pattern given on the command line takes precedence over the saved one.
+    /// Collapsed subtrees are recognized by the name and arguments of their root process.
+    no_persist: bool,
+
+    #[arg(long, value_name = "FILE")]
+    /// Append total cpu and ram usage to FILE (as csv) on every refresh
+    log: Option<PathBuf>,
+}
+
+impl Args {
+    const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Applies settings from environment variables, for wrapper scripts that can't easily pass
+    /// flags.
+    fn with_env(mut self, var: impl Fn(&str) -> Option<String>) -> Args {
+        let enabled =
+            |name| var(name).is_some_and(|value| !matches!(value.as_str(), "" | "0" | "false"));
+        self.dont_hide_self |= enabled("TREETOP_DONT_HIDE_SELF");
+        // see https://no-color.org: any non-empty value disables colors
+        self.no_color |= var("NO_COLOR").is_some_and(|value| !value.is_empty());
+        self
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval).max(Args::MIN_INTERVAL)
+    }
 }
 
-fn main() -> R<()> {
-    let args = Args::parse();
+#[cfg(test)]
+impl Default for Args {
+    fn default() -> Self {
+        Args::parse_from(["treetop"])
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            // 1 means that no processes matched, see '--print'
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run() -> R<ExitCode> {
+    let args = Args::parse().with_env(|name| std::env::var(name).ok());
     if args.help {
         Args::command().print_long_help()?;
     } else {
-        TreetopApp::run(TreetopApp::new(ProcessWatcher::new(System::new()), args)?)?;
+        let (print, json, top) = (args.print, args.json, args.top);
+        let mut process_watcher = match &args.proc_dir {
+            Some(proc_dir) => ProcessWatcher::from_proc_dir(ProcDir::new(proc_dir.clone())),
+            None => ProcessWatcher::new(System::new(), args.search_cwd),
+        };
+        // without a warm-up, --top would list arbitrary processes with 0% cpu
+        if args.warmup || top.is_some() {
+            process_watcher.warm_up();
+        }
+        let state_file = if args.no_persist || print || json || top.is_some() {
+            StateFile::disabled()
+        } else {
+            StateFile::new()
+        };
+        let app = TreetopApp::new(process_watcher, Box::new(KillSignaler), state_file, args)?;
+        let found_matches = if json {
+            app.print_json()?
+        } else if let Some(count) = top {
+            app.print_top(count)?
+        } else if print {
+            app.print()?
+        } else {
+            app.run()?;
+            true
+        };
+        if !found_matches {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interval_defaults_to_one_second() {
+        assert_eq!(Args::default().interval(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn interval_can_be_configured() {
+        let args = Args::parse_from(["treetop", "--interval", "250"]);
+        assert_eq!(args.interval(), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn dont_hide_self_can_be_set_through_the_environment() {
+        let env = |value: &'static str| {
+            move |name: &str| (name == "TREETOP_DONT_HIDE_SELF").then(|| value.to_string())
+        };
+        assert!(Args::default().with_env(env("1")).dont_hide_self);
+        assert!(!Args::default().with_env(env("0")).dont_hide_self);
+        assert!(!Args::default().with_env(|_| None).dont_hide_self);
+        let args = Args::parse_from(["treetop", "--dont-hide-self"]);
+        assert!(args.with_env(env("false")).dont_hide_self);
+    }
+
+    #[test]
+    fn no_color_can_be_set_through_the_environment() {
+        let env =
+            |value: &'static str| move |name: &str| (name == "NO_COLOR").then(|| value.to_string());
+        assert!(Args::default().with_env(env("1")).no_color);
+        assert!(Args::default().with_env(env("0")).no_color);
+        assert!(!Args::default().with_env(env("")).no_color);
+        assert!(!Args::default().with_env(|_| None).no_color);
+    }
+
+    #[test]
+    fn unknown_sort_columns_are_rejected() {
+        assert!(Args::try_parse_from(["treetop", "--sort", "size"]).is_err());
+    }
+
+    #[test]
+    fn unknown_columns_are_rejected() {
+        assert!(Args::try_parse_from(["treetop", "--columns", "pid,size"]).is_err());
+        assert!(Args::try_parse_from(["treetop", "--columns", "ram-percent,pid"]).is_ok());
+    }
+
+    #[test]
+    fn interval_is_clamped() {
+        let args = Args::parse_from(["treetop", "--interval", "10"]);
+        assert_eq!(args.interval(), Duration::from_millis(100));
     }
-    Ok(())
 }
@@ -0,0 +1,91 @@
+const MAX_ENTRIES: usize = 100;
+
+/// Browser-like history of visited entries.
+#[derive(Debug)]
+pub(crate) struct NavigationHistory<T> {
+    entries: Vec<T>,
+    position: usize,
+}
+
+impl<T: Copy + PartialEq> NavigationHistory<T> {
+    pub(crate) fn new() -> Self {
+        NavigationHistory {
+            entries: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Records a visit. Drops all entries that could be reached with `forward`.
+    pub(crate) fn push(&mut self, entry: T) {
+        if self.entries.get(self.position) == Some(&entry) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.position + 1);
+        }
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    pub(crate) fn back(&mut self) -> Option<T> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        self.entries.get(self.position).copied()
+    }
+
+    pub(crate) fn forward(&mut self) -> Option<T> {
+        if self.position + 1 >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        self.entries.get(self.position).copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn goes_back_and_forward() {
+        let mut history = NavigationHistory::new();
+        history.push(1);
+        history.push(2);
+        history.push(3);
+        assert_eq!(history.back(), Some(2));
+        assert_eq!(history.back(), Some(1));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(2));
+        assert_eq!(history.forward(), Some(3));
+        assert_eq!(history.forward(), None);
+    }
+
+    #[test]
+    fn pushing_drops_forward_entries() {
+        let mut history = NavigationHistory::new();
+        history.push(1);
+        history.push(2);
+        history.back();
+        history.push(3);
+        assert_eq!(history.forward(), None);
+        assert_eq!(history.back(), Some(1));
+    }
+
+    #[test]
+    fn is_bounded() {
+        let mut history = NavigationHistory::new();
+        for i in 0..(MAX_ENTRIES + 10) {
+            history.push(i);
+        }
+        let mut count = 0;
+        while history.back().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, MAX_ENTRIES - 1);
+    }
+}
@@ -0,0 +1,240 @@
+#[cfg(test)]
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A directory laid out like /proc, e.g. a copy captured for post-mortem debugging.
+#[derive(Debug)]
+pub(crate) struct ProcDir(ProcDirInner);
+
+#[derive(Debug)]
+enum ProcDirInner {
+    Directory(PathBuf),
+    #[cfg(test)]
+    Fake(BTreeMap<String, String>),
+}
+
+/// The parts of /proc/<pid>/stat that treetop uses.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Stat {
+    pub(crate) pid: u32,
+    pub(crate) name: String,
+    pub(crate) state: char,
+    pub(crate) parent: Option<u32>,
+    pub(crate) rss_pages: u64,
+    /// Clock ticks between system boot and the start of the process
+    pub(crate) start_ticks: u64,
+    pub(crate) kernel_thread: bool,
+}
+
+/// The `PF_KTHREAD` bit of the flags field in /proc/<pid>/stat
+const KERNEL_THREAD_FLAG: u64 = 0x0020_0000;
+
+impl ProcDir {
+    const PAGE_SIZE: u64 = 4096;
+    const TICKS_PER_SECOND: u64 = 100;
+
+    pub(crate) fn new(path: PathBuf) -> ProcDir {
+        ProcDir(ProcDirInner::Directory(path))
+    }
+
+    fn read(&self, path: &str) -> Option<String> {
+        match self {
+            ProcDir(ProcDirInner::Directory(dir)) => {
+                let bytes = fs::read(dir.join(path)).ok()?;
+                Some(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            #[cfg(test)]
+            ProcDir(ProcDirInner::Fake(files)) => files.get(path).cloned(),
+        }
+    }
+
+    fn pids(&self) -> Vec<u32> {
+        match self {
+            ProcDir(ProcDirInner::Directory(dir)) => fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect(),
+            #[cfg(test)]
+            ProcDir(ProcDirInner::Fake(files)) => {
+                let mut pids: Vec<u32> = files
+                    .keys()
+                    .filter_map(|path| path.split('/').next()?.parse().ok())
+                    .collect();
+                pids.dedup();
+                pids
+            }
+        }
+    }
+
+    /// Reads the stat and cmdline files of all processes. Processes with unreadable or
+    /// malformed stat files are skipped.
+    pub(crate) fn processes(&self) -> Vec<(Stat, Vec<String>)> {
+        self.pids()
+            .into_iter()
+            .filter_map(|pid| {
+                let stat = parse_stat(&self.read(&format!("{pid}/stat"))?)?;
+                let cmdline = self
+                    .read(&format!("{pid}/cmdline"))
+                    .map(|cmdline| parse_cmdline(&cmdline))
+                    .unwrap_or_default();
+                Some((stat, cmdline))
+            })
+            .collect()
+    }
+
+    /// Total memory in bytes, as found in meminfo.
+    pub(crate) fn total_memory(&self) -> u64 {
+        self.meminfo_field("MemTotal").unwrap_or(0)
+    }
+
+    /// Memory in bytes that is not available for starting new applications.
+    pub(crate) fn used_memory(&self) -> u64 {
+        match (
+            self.meminfo_field("MemTotal"),
+            self.meminfo_field("MemAvailable"),
+        ) {
+            (Some(total), Some(available)) => total.saturating_sub(available),
+            _ => 0,
+        }
+    }
+
+    fn meminfo_field(&self, field: &str) -> Option<u64> {
+        parse_meminfo_field(&self.read("meminfo")?, field)
+    }
+
+    /// Seconds the process had been running when the snapshot was taken, according to the
+    /// top-level 'uptime' file. 0 if that file is missing.
+    pub(crate) fn process_uptime(&self, stat: &Stat) -> u64 {
+        let system_uptime = self
+            .read("uptime")
+            .and_then(|uptime| uptime.split('.').next()?.trim().parse::<u64>().ok());
+        match system_uptime {
+            Some(system_uptime) => {
+                system_uptime.saturating_sub(stat.start_ticks / ProcDir::TICKS_PER_SECOND)
+            }
+            None => 0,
+        }
+    }
+
+    pub(crate) fn ram(stat: &Stat) -> u64 {
+        stat.rss_pages * ProcDir::PAGE_SIZE
+    }
+}
+
+fn parse_stat(contents: &str) -> Option<Stat> {
+    // The name is in parentheses and may itself contain spaces and parentheses.
+    let (pid, rest) = contents.split_once(" (")?;
+    let (name, rest) = rest.rsplit_once(") ")?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    Some(Stat {
+        pid: pid.trim().parse().ok()?,
+        name: name.to_string(),
+        state: fields.first()?.chars().next()?,
+        parent: match fields.get(1)?.parse().ok()? {
+            0 => None,
+            parent => Some(parent),
+        },
+        start_ticks: fields.get(19)?.parse().ok()?,
+        rss_pages: fields.get(21)?.parse().ok()?,
+        kernel_thread: fields.get(6)?.parse::<u64>().ok()? & KERNEL_THREAD_FLAG != 0,
+    })
+}
+
+fn parse_cmdline(contents: &str) -> Vec<String> {
+    contents
+        .split('\0')
+        .filter(|argument| !argument.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn parse_meminfo_field(meminfo: &str, field: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| {
+        line.strip_prefix(field)
+            .is_some_and(|rest| rest.starts_with(':'))
+    })?;
+    let kilobytes: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    impl ProcDir {
+        pub(crate) fn fake(files: &[(&str, &str)]) -> ProcDir {
+            ProcDir(ProcDirInner::Fake(
+                files
+                    .iter()
+                    .map(|(path, contents)| ((*path).to_string(), (*contents).to_string()))
+                    .collect(),
+            ))
+        }
+    }
+
+    pub(crate) fn stat(pid: u32, name: &str, parent: u32) -> String {
+        format!(
+            "{pid} ({name}) S {parent} 1 1 0 -1 4194560 0 0 0 0 0 0 0 0 20 0 1 0 1000 0 256 0 0"
+        )
+    }
+
+    #[test]
+    fn parses_stat_files() {
+        assert_eq!(
+            parse_stat(&stat(42, "bash", 1)),
+            Some(Stat {
+                pid: 42,
+                name: "bash".to_string(),
+                state: 'S',
+                parent: Some(1),
+                rss_pages: 256,
+                start_ticks: 1000,
+                kernel_thread: false,
+            })
+        );
+    }
+
+    #[test]
+    fn detects_kernel_threads() {
+        let stat = "2 (kthreadd) S 0 0 0 0 -1 2129984 0 0 0 0 0 0 0 0 20 0 1 0 10 0 0 0 0";
+        assert_eq!(parse_stat(stat).map(|stat| stat.kernel_thread), Some(true));
+    }
+
+    #[test]
+    fn parses_names_with_spaces_and_parentheses() {
+        assert_eq!(
+            parse_stat(&stat(7, "tmux: server (1)", 0)).map(|stat| (stat.name, stat.parent)),
+            Some(("tmux: server (1)".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn computes_process_uptimes_from_the_system_uptime() -> Result<(), &'static str> {
+        let proc_dir = ProcDir::fake(&[("uptime", "70.25 120.50\n")]);
+        let stat = parse_stat(&stat(42, "bash", 1)).ok_or("invalid stat")?;
+        assert_eq!(proc_dir.process_uptime(&stat), 60);
+        Ok(())
+    }
+
+    #[test]
+    fn splits_cmdline_at_null_bytes() {
+        assert_eq!(parse_cmdline("sleep\u{0}10\u{0}"), vec!["sleep", "10"]);
+    }
+
+    #[test]
+    fn reads_total_memory_from_meminfo() {
+        let proc_dir = ProcDir::fake(&[("meminfo", "MemTotal:       16384 kB\nMemFree: 1 kB\n")]);
+        assert_eq!(proc_dir.total_memory(), 16 * 2_u64.pow(20));
+    }
+
+    #[test]
+    fn computes_used_memory_from_meminfo() {
+        let proc_dir = ProcDir::fake(&[(
+            "meminfo",
+            "MemTotal: 16384 kB\nMemFree: 1024 kB\nMemAvailable: 4096 kB\n",
+        )]);
+        assert_eq!(proc_dir.used_memory(), 12 * 2_u64.pow(20));
+    }
+}
@@ -1,44 +1,95 @@
+use crate::proc_dir::ProcDir;
+use crate::proc_dir::Stat;
 use crate::regex::Regex;
+use crate::theme::{Theme, Usage};
 pub(crate) use crate::tree::Forest;
 use crate::tree::Node;
+use crate::utils::truncate_to_width;
 use crate::Args;
+use crate::R;
 use num_format::Locale;
 use num_format::ToFormattedString;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
 use ratatui::prelude::Stylize;
-use ratatui::style::Modifier;
-use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use sysinfo::Pid;
 use sysinfo::ProcessRefreshKind;
+use sysinfo::ProcessStatus;
 use sysinfo::ThreadKind;
 use sysinfo::UpdateKind;
+use sysinfo::Users;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub(crate) struct Process {
+    #[serde(serialize_with = "serialize_pid")]
     pid: Pid,
     pub(crate) name: String,
     pub(crate) arguments: Vec<String>,
+    /// Full path of the executable, if known
+    #[serde(skip)]
+    exe: Option<String>,
+    #[serde(skip)]
+    show_full_path: bool,
+    /// Current working directory, if known
+    #[serde(skip)]
+    cwd: Option<PathBuf>,
+    #[serde(skip)]
     parent: Option<Pid>,
+    /// Only resolved for 'parent:' patterns, see `resolve_parent_name`
+    #[serde(skip)]
+    parent_name: Option<String>,
     cpu: f32,
     ram: u64,
+    /// Usage of the process itself, without the usage accumulated from its descendants
+    #[serde(skip)]
+    own_cpu: f32,
+    #[serde(skip)]
+    own_ram: u64,
+    /// Bytes read from and written to disk per second, including descendants like `cpu` and
+    /// `ram`
+    #[serde(skip)]
+    disk_read: u64,
+    #[serde(skip)]
+    disk_write: u64,
+    #[serde(skip)]
+    own_disk_read: u64,
+    #[serde(skip)]
+    own_disk_write: u64,
+    /// Number of open file descriptors. `None` if they can't be read, e.g. for lack of
+    /// permissions. Unlike the usage columns, this doesn't include descendants.
+    #[serde(skip)]
+    fds: Option<usize>,
+    /// Seconds since the process was started
+    #[serde(skip)]
+    uptime: u64,
+    #[serde(skip)]
+    user: Option<String>,
+    #[serde(skip)]
+    status: ProcessStatus,
+    #[serde(skip)]
+    kernel_thread: bool,
+    /// Whether this is a userland thread of its parent process
+    #[serde(skip)]
+    thread: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_pid<S: Serializer>(pid: &Pid, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(pid.as_u32())
 }
 
 impl fmt::Display for Process {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.arguments.first() {
-            Some(executable) => match Path::new(&executable).file_name() {
-                Some(file_name) => write!(f, "{}", file_name.to_string_lossy())?,
-                None => write!(f, "{executable}")?,
-            },
-            None => write!(f, "{}", self.name)?,
-        }
-        for argument in self.arguments.iter().skip(1) {
-            write!(f, " {argument}")?;
+        write!(f, "{}", self.command())?;
+        if self.is_zombie() && !self.thread {
+            write!(f, " (defunct)")?;
         }
         Ok(())
     }
@@ -55,14 +106,25 @@ impl Node for Process {
         self.parent
     }
 
+    /// Threads are skipped, since their usage is already included in their process's usage.
     fn accumulate_from(&mut self, other: &Self) {
+        if other.thread {
+            return;
+        }
         self.cpu += other.cpu;
         self.ram += other.ram;
+        self.disk_read += other.disk_read;
+        self.disk_write += other.disk_write;
     }
 }
 
 impl Process {
-    fn from_sysinfo_process(process: &sysinfo::Process) -> Self {
+    fn from_sysinfo_process(
+        process: &sysinfo::Process,
+        users: &Users,
+        (disk_read, disk_write): (u64, u64),
+        fds: Option<usize>,
+    ) -> Self {
         Process {
             pid: process.pid(),
             name: match process.exe() {
@@ -73,55 +135,325 @@ impl Process {
                 None => process.name().to_string(),
             },
             arguments: process.cmd().to_vec(),
+            exe: process.exe().map(|exe| exe.to_string_lossy().to_string()),
+            show_full_path: false,
+            cwd: process.cwd().map(Path::to_path_buf),
             parent: process.parent(),
+            parent_name: None,
             cpu: process.cpu_usage(),
             ram: process.memory(),
+            own_cpu: process.cpu_usage(),
+            own_ram: process.memory(),
+            disk_read,
+            disk_write,
+            own_disk_read: disk_read,
+            own_disk_write: disk_write,
+            fds,
+            uptime: process.run_time(),
+            user: process
+                .user_id()
+                .and_then(|user_id| users.get_user_by_id(user_id))
+                .map(|user| user.name().to_string()),
+            status: process.status(),
+            kernel_thread: process.thread_kind() == Some(ThreadKind::Kernel),
+            thread: process.thread_kind() == Some(ThreadKind::Userland),
+        }
+    }
+
+    /// Cpu usage and disk throughput can't be computed from a single snapshot, so they're
+    /// always 0.
+    fn from_proc_dir_entry(stat: &Stat, arguments: Vec<String>, uptime: u64) -> Self {
+        Process {
+            pid: Pid::from_u32(stat.pid),
+            name: stat.name.clone(),
+            arguments,
+            exe: None,
+            show_full_path: false,
+            cwd: None,
+            parent: stat.parent.map(Pid::from_u32),
+            parent_name: None,
+            cpu: 0.0,
+            ram: ProcDir::ram(stat),
+            own_cpu: 0.0,
+            own_ram: ProcDir::ram(stat),
+            disk_read: 0,
+            disk_write: 0,
+            own_disk_read: 0,
+            own_disk_write: 0,
+            fds: None,
+            uptime,
+            user: None,
+            status: ProcessStatus::from(stat.state),
+            kernel_thread: stat.kernel_thread,
+            thread: false,
+        }
+    }
+
+    /// No real process has pid 0, so it's used for the synthetic node that kernel threads are
+    /// grouped under.
+    const KERNEL_GROUP_PID: u32 = 0;
+
+    fn kernel_group() -> Process {
+        Process {
+            pid: Pid::from_u32(Process::KERNEL_GROUP_PID),
+            name: "[kernel]".to_string(),
+            arguments: Vec::new(),
+            exe: None,
+            show_full_path: false,
+            cwd: None,
+            parent: None,
+            parent_name: None,
+            cpu: 0.0,
+            ram: 0,
+            own_cpu: 0.0,
+            own_ram: 0,
+            disk_read: 0,
+            disk_write: 0,
+            own_disk_read: 0,
+            own_disk_write: 0,
+            fds: None,
+            uptime: 0,
+            user: None,
+            status: ProcessStatus::Unknown(0),
+            kernel_thread: false,
+            thread: false,
+        }
+    }
+
+    /// Identifies the process across runs, unlike its pid, by hashing its name and arguments.
+    /// Uses FNV-1a, since the output of `std`'s hashers may change between Rust versions.
+    pub(crate) fn collapse_key(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for part in std::iter::once(&self.name).chain(&self.arguments) {
+            for byte in part.bytes().chain([0]) {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+        hash
+    }
+
+    /// Whether this is a node that doesn't correspond to a real process, and thus can't be
+    /// sent signals.
+    pub(crate) fn is_synthetic(&self) -> bool {
+        self.pid.as_u32() == Process::KERNEL_GROUP_PID
+    }
+
+    /// Moves all kernel threads directly below a synthetic '[kernel]' root.
+    fn group_kernel_threads(processes: Vec<Process>) -> Vec<Process> {
+        let mut result: Vec<Process> = processes
+            .into_iter()
+            .map(|mut process| {
+                if process.kernel_thread {
+                    process.parent = Some(Pid::from_u32(Process::KERNEL_GROUP_PID));
+                }
+                process
+            })
+            .collect();
+        if result.iter().any(|process| process.kernel_thread) {
+            result.push(Process::kernel_group());
         }
+        result
     }
 
-    pub(crate) fn compare(&self, other: &Process, sort_by: SortBy) -> std::cmp::Ordering {
+    /// With `stable`, cpu usages are rounded to whole percents before comparing them, so that
+    /// small fluctuations between refreshes don't reorder processes.
+    pub(crate) fn compare(
+        &self,
+        other: &Process,
+        sort_by: SortBy,
+        descending: bool,
+        stable: bool,
+    ) -> std::cmp::Ordering {
         let ordering = match sort_by {
             SortBy::Pid => self.id().partial_cmp(&other.id()),
-            SortBy::Cpu => other.cpu.partial_cmp(&self.cpu),
-            SortBy::Ram => other.ram.partial_cmp(&self.ram),
+            SortBy::Cpu if stable => self.cpu.round().partial_cmp(&other.cpu.round()),
+            SortBy::Cpu => self.cpu.partial_cmp(&other.cpu),
+            SortBy::Ram => self.ram.partial_cmp(&other.ram),
+            SortBy::Uptime => self.uptime.partial_cmp(&other.uptime),
+            SortBy::DiskRead => self.disk_read.partial_cmp(&other.disk_read),
+            SortBy::DiskWrite => self.disk_write.partial_cmp(&other.disk_write),
+            SortBy::Fds => self.fds.partial_cmp(&other.fds),
+            SortBy::Name => Some(
+                self.display_name()
+                    .to_lowercase()
+                    .cmp(&other.display_name().to_lowercase()),
+            ),
         };
-        match ordering {
+        let ordering = match ordering {
             Some(std::cmp::Ordering::Equal) | None => self.pid.cmp(&other.pid),
             Some(ordering) => ordering,
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    pub(crate) fn cpu(&self) -> f32 {
+        self.cpu
+    }
+
+    pub(crate) fn ram(&self) -> u64 {
+        self.ram
+    }
+
+    pub(crate) fn make_cpu_relative_to_quota(&mut self, cores: f32) {
+        self.cpu /= cores;
+        self.own_cpu /= cores;
+    }
+
+    /// The name shown in the tree, before the arguments: the file name of the executable as it
+    /// was started (or its full path, see `show_full_path`), or `name` if that's unknown.
+    fn display_name(&self) -> String {
+        match self.arguments.first() {
+            _ if self.thread => self.name.clone(),
+            Some(executable) if self.show_full_path => {
+                self.exe.clone().unwrap_or_else(|| executable.clone())
+            }
+            Some(executable) => match Path::new(&executable).file_name() {
+                Some(file_name) => file_name.to_string_lossy().to_string(),
+                None => executable.clone(),
+            },
+            None => self.name.clone(),
         }
     }
 
+    /// The command as shown in the tree, but without the ' (defunct)' label of zombies.
+    pub(crate) fn command(&self) -> String {
+        let mut result = self.display_name();
+        // threads share the command line of their process
+        if !self.thread {
+            for argument in self.arguments.iter().skip(1) {
+                result.push(' ');
+                result.push_str(argument);
+            }
+        }
+        result
+    }
+
+    /// Displays the full path of the executable instead of just its file name.
+    pub(crate) fn show_full_path(&mut self) {
+        self.show_full_path = true;
+    }
+
+    /// Drops the usage accumulated from descendants.
+    pub(crate) fn show_own_usage(&mut self) {
+        self.cpu = self.own_cpu;
+        self.ram = self.own_ram;
+        self.disk_read = self.own_disk_read;
+        self.disk_write = self.own_disk_write;
+    }
+
+    /// Looks up the name of the parent process in `names`, which maps pids to names.
+    pub(crate) fn resolve_parent_name(&mut self, names: &HashMap<Pid, String>) {
+        self.parent_name = self.parent.and_then(|parent| names.get(&parent).cloned());
+    }
+
+    /// 'parent:' patterns match processes whose parent's name matches, which requires
+    /// `resolve_parent_name` to have been called.
     pub(crate) fn is_match(&self, pattern: &Regex, treetop_pid: Pid, args: &Args) -> bool {
+        if let Some(terms) = pattern.terms() {
+            return terms
+                .iter()
+                .any(|term| self.is_match(term, treetop_pid, args));
+        }
+        if let Some(range) = pattern.pid_range() {
+            return range.contains(&self.id().as_u32());
+        }
+        if let Some(user_pattern) = pattern.user_pattern() {
+            return self
+                .user
+                .as_deref()
+                .is_some_and(|user| user_pattern.is_match(user));
+        }
+        if let Some(parent_pattern) = pattern.parent_pattern() {
+            return self
+                .parent_name
+                .as_deref()
+                .is_some_and(|name| parent_pattern.is_match(name));
+        }
         if pattern.is_match(&self.name) {
             return true;
         }
         if pattern.is_match(&self.id().to_string()) {
             return true;
         }
-        if pattern.is_match(&self.arguments.join(" ")) {
+        if pattern.is_match(&self.arguments.join(args.argument_separator.as_str())) {
             return args.dont_hide_self || treetop_pid != self.id();
         }
+        if args.match_user {
+            if let Some(user) = &self.user {
+                if pattern.is_match(user) {
+                    return true;
+                }
+            }
+        }
+        if args.search_cwd {
+            if let Some(cwd) = &self.cwd {
+                if pattern.is_match(&cwd.to_string_lossy()) {
+                    return true;
+                }
+            }
+        }
         false
     }
 
-    pub(crate) fn render_header(area: Rect, sort_by: SortBy, buffer: &mut Buffer) -> u16 {
+    /// Whether the process is owned by the user with exactly this name.
+    pub(crate) fn is_owned_by(&self, user: &str) -> bool {
+        self.user.as_deref() == Some(user)
+    }
+
+    /// Whether this is treetop's own process and it's hidden although it matches the pattern.
+    pub(crate) fn is_hidden_self_match(
+        &self,
+        pattern: &Regex,
+        treetop_pid: Pid,
+        args: &Args,
+    ) -> bool {
+        !args.dont_hide_self
+            && treetop_pid == self.id()
+            && !self.is_match(pattern, treetop_pid, args)
+            && pattern.is_match(&self.arguments.join(args.argument_separator.as_str()))
+    }
+
+    pub(crate) fn render_header(
+        area: Rect,
+        args: &Args,
+        pid_width: usize,
+        sort_by: SortBy,
+        descending: bool,
+        buffer: &mut Buffer,
+    ) -> u16 {
+        let (enabled, style, theme) = (&args.sort_columns, args.tree_style, args.theme);
         let table_header = {
             let mut line = Line::default();
-            for column in SortBy::all() {
-                let leading_spaces = match column {
-                    SortBy::Pid => 5,
-                    SortBy::Cpu => 3,
-                    SortBy::Ram => 7,
+            for column in &args.columns {
+                let width = match column {
+                    Column::Pid => pid_width,
+                    _ => column.width(),
                 };
-                line.push_span(" ".repeat(leading_spaces));
-                line.push_span(Span::styled(
-                    format!("{column:?}").to_lowercase(),
-                    if column == sort_by {
-                        Style::new().add_modifier(Modifier::REVERSED)
-                    } else {
-                        Style::new()
-                    },
-                ));
+                let label = match column {
+                    // left-aligned like its cells
+                    Column::User => format!("{:<width$}", column.label()),
+                    _ => column.label().to_string(),
+                };
+                let leading_spaces = 1 + width.saturating_sub(label.len());
+                let spans = match column.sort_by() {
+                    Some(column) => header_label(
+                        label.clone(),
+                        leading_spaces,
+                        column == sort_by,
+                        descending,
+                        enabled.contains(&column),
+                        theme,
+                    ),
+                    None => header_label(label, leading_spaces, false, false, true, theme),
+                };
+                for span in spans {
+                    line.push_span(span);
+                }
             }
             line.push_span(" ");
             line
@@ -129,44 +461,392 @@ impl Process {
         buffer.set_line(area.x, area.y, &table_header, area.width);
         if let Ok(table_header_length) = table_header.width().try_into() {
             if let Some(cell) = buffer.cell_mut((table_header_length, area.y)) {
-                cell.set_symbol("┃");
-                cell.set_style(Style::new().dark_gray());
+                cell.set_symbol(style.column_separator());
+                cell.set_style(theme.dimmed());
             }
-            buffer.set_string(
-                area.x + table_header_length + 2,
+            buffer.set_line(
+                area.x + table_header_length + 1,
                 area.y,
-                "executable",
-                Style::new(),
+                &Line::from(header_label(
+                    "name".to_string(),
+                    1,
+                    sort_by == SortBy::Name,
+                    descending,
+                    enabled.contains(&SortBy::Name),
+                    theme,
+                )),
+                area.width.saturating_sub(table_header_length + 1),
             );
             for x in (area.x)..(area.width) {
                 if let Some(cell) = buffer.cell_mut((x, area.y + 1)) {
                     cell.set_symbol(if x == table_header_length {
-                        "╋"
+                        style.header_junction()
                     } else {
-                        "━"
+                        style.header_rule()
                     });
-                    cell.set_style(Style::new().dark_gray());
+                    cell.set_style(theme.dimmed());
                 }
             }
         }
         2
     }
 
-    pub(crate) fn table_data(&self) -> String {
-        format!(
-            "{:>8} {:>4.0}% {:>7}MB",
-            self.pid.as_u32(),
-            self.cpu,
+    /// The cells of the given columns, each preceded by a space. Pids are right-aligned to
+    /// `pid_width`, see `Process::pid_width`.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn table_data(
+        &self,
+        columns: &[Column],
+        pid_width: usize,
+        total_memory: u64,
+        theme: Theme,
+    ) -> Vec<Span<'static>> {
+        let ram_percentage = if total_memory == 0 {
+            0.0
+        } else {
+            self.ram as f64 / total_memory as f64 * 100.0
+        };
+        let mut result = Vec::new();
+        for column in columns {
+            result.push(Span::raw(" "));
+            result.push(match column {
+                Column::Pid => Span::raw(format!(
+                    "{:>pid_width$}",
+                    if self.is_synthetic() {
+                        String::new()
+                    } else {
+                        self.pid.to_string()
+                    }
+                )),
+                Column::Cpu => self.cpu_span(theme),
+                Column::Ram => self.ram_span(ram_percentage, total_memory, theme),
+                Column::Uptime => Span::raw(format!("{:>6}", format_uptime(self.uptime))),
+                Column::Read => Span::raw(format!("{:>6}", format_rate(self.disk_read))),
+                Column::Write => Span::raw(format!("{:>6}", format_rate(self.disk_write))),
+                Column::Fds => Span::raw(format!(
+                    "{:>5}",
+                    match self.fds {
+                        Some(fds) => fds.to_string(),
+                        None => "-".to_string(),
+                    }
+                )),
+                Column::RamPercent => Span::raw(format!("{ram_percentage:>5.1}%")),
+                Column::User => Span::raw(format!(
+                    "{:<8}",
+                    match &self.user {
+                        Some(user) => truncate_to_width(user, 8).0,
+                        None => "-".to_string(),
+                    }
+                )),
+                Column::Status => self.status_glyph(theme),
+            });
+        }
+        result
+    }
+
+    /// The lines of the details pane shown for the selected process: the whole command, the
+    /// working directory (see `ProcessWatcher::cwd`), and the user, start time and usage.
+    pub(crate) fn details(&self, cwd: Option<&Path>) -> Vec<Line<'static>> {
+        let mb = |bytes: u64| {
+            format!(
+                "{}MB",
+                (bytes / 2_u64.pow(20)).to_formatted_string(&Locale::en)
+            )
+        };
+        let field = |label: &str, value: String| {
+            vec![Span::raw(format!("{label}: ")).bold(), Span::raw(value)]
+        };
+        let mut usage = Vec::new();
+        for (i, (label, value)) in [
+            ("user", self.user.clone().unwrap_or_else(|| "-".to_string())),
+            ("started", format!("{} ago", format_uptime(self.uptime))),
+            (
+                "cpu",
+                format!("{:.0}% (own {:.0}%)", self.cpu, self.own_cpu),
+            ),
+            (
+                "ram",
+                format!("{} (own {})", mb(self.ram), mb(self.own_ram)),
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            if i > 0 {
+                usage.push(Span::raw(" | "));
+            }
+            usage.extend(field(label, value));
+        }
+        vec![
+            Line::from(field("command", self.full_command())),
+            Line::from(field(
+                "cwd",
+                match cwd {
+                    Some(cwd) => cwd.to_string_lossy().to_string(),
+                    None => "-".to_string(),
+                },
+            )),
+            Line::from(usage),
+        ]
+    }
+
+    /// Like `Display`, but with the executable as it was started, including its path.
+    fn full_command(&self) -> String {
+        if self.thread || self.arguments.is_empty() {
+            return self.to_string();
+        }
+        let mut result = self.arguments.join(" ");
+        if self.is_zombie() {
+            result.push_str(" (defunct)");
+        }
+        result
+    }
+
+    /// Width of the pid column that fits all the given processes, but at least the default width.
+    pub(crate) fn pid_width<'a>(processes: impl Iterator<Item = &'a Process>) -> usize {
+        processes
+            .filter(|p| !p.is_synthetic())
+            .map(|p| p.pid.to_string().len())
+            .fold(Column::Pid.width(), usize::max)
+    }
+
+    /// Cpu usage (in percent) from which on the cpu column is shown in yellow
+    const CPU_WARNING: f32 = 25.0;
+    /// Cpu usage (in percent) above which the cpu column is shown in red
+    const CPU_CRITICAL: f32 = 75.0;
+
+    fn cpu_span(&self, theme: Theme) -> Span<'static> {
+        let usage = if self.cpu > Process::CPU_CRITICAL {
+            Usage::Critical
+        } else if self.cpu >= Process::CPU_WARNING {
+            Usage::Warning
+        } else {
+            Usage::Low
+        };
+        Span::styled(format!("{:>4.0}%", self.cpu), theme.usage(usage))
+    }
+
+    /// Share of total memory (in percent) from which on the ram column is shown in yellow
+    const RAM_WARNING: f64 = 10.0;
+    /// Share of total memory (in percent) above which the ram column is shown in red
+    const RAM_CRITICAL: f64 = 25.0;
+
+    fn ram_span(&self, ram_percentage: f64, total_memory: u64, theme: Theme) -> Span<'static> {
+        let span = Span::raw(format!(
+            "{:>7}MB",
             (self.ram / 2_u64.pow(20)).to_formatted_string(&Locale::en)
-        )
+        ));
+        if total_memory == 0 {
+            span
+        } else if ram_percentage > Process::RAM_CRITICAL {
+            span.style(theme.usage(Usage::Critical))
+        } else if ram_percentage >= Process::RAM_WARNING {
+            span.style(theme.usage(Usage::Warning))
+        } else {
+            span.style(theme.usage(Usage::Low))
+        }
+    }
+
+    /// Whether the process exited, but wasn't reaped by its parent yet. Zombies keep the command
+    /// they were started with, as long as they were seen alive before.
+    fn is_zombie(&self) -> bool {
+        matches!(self.status, ProcessStatus::Zombie | ProcessStatus::Dead)
+    }
+
+    fn status_glyph(&self, theme: Theme) -> Span<'static> {
+        if self.is_synthetic() {
+            return Span::raw(" ");
+        }
+        match self.status {
+            ProcessStatus::Run | ProcessStatus::Waking => Span::raw("R"),
+            ProcessStatus::Sleep | ProcessStatus::Idle | ProcessStatus::Parked => Span::raw("S"),
+            ProcessStatus::UninterruptibleDiskSleep | ProcessStatus::LockBlocked => Span::raw("D"),
+            ProcessStatus::Zombie | ProcessStatus::Dead => {
+                Span::styled("Z", theme.usage(Usage::Critical))
+            }
+            ProcessStatus::Stop | ProcessStatus::Tracing | ProcessStatus::Wakekill => {
+                Span::styled("T", theme.usage(Usage::Warning))
+            }
+            ProcessStatus::Unknown(_) => Span::raw("?"),
+        }
+    }
+}
+
+/// Formats a duration in seconds with its two most significant units, e.g. "1d03h" or "12m".
+fn format_uptime(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{days}d{hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h{minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Formats bytes per second with a binary unit, e.g. "512B", "1.5K" or "12M".
+#[allow(clippy::cast_precision_loss)]
+fn format_rate(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes}B")
+    } else if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+fn header_label(
+    text: String,
+    leading_spaces: usize,
+    is_sort_column: bool,
+    descending: bool,
+    is_enabled: bool,
+    theme: Theme,
+) -> Vec<Span<'static>> {
+    if is_sort_column {
+        vec![
+            Span::raw(" ".repeat(leading_spaces - 1)),
+            Span::styled(
+                format!("{}{text}", if descending { "▼" } else { "▲" }),
+                theme.sort_column(),
+            ),
+        ]
+    } else if is_enabled {
+        vec![Span::raw(" ".repeat(leading_spaces)), Span::raw(text)]
+    } else {
+        vec![
+            Span::raw(" ".repeat(leading_spaces)),
+            Span::styled(text, theme.dimmed()),
+        ]
+    }
+}
+
+/// How process arguments are joined before matching them against the search pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum ArgumentSeparator {
+    /// Join with single spaces, like a shell command line
+    #[default]
+    Space,
+    /// Join without any separator
+    None,
+    /// Join with newlines
+    Newline,
+}
+
+impl ArgumentSeparator {
+    fn as_str(self) -> &'static str {
+        match self {
+            ArgumentSeparator::Space => " ",
+            ArgumentSeparator::None => "",
+            ArgumentSeparator::Newline => "\n",
+        }
+    }
+}
+
+/// The columns shown left of the tree. The name column is always shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Column {
+    Pid,
+    Cpu,
+    Ram,
+    Uptime,
+    /// Bytes read from disk per second
+    Read,
+    /// Bytes written to disk per second
+    Write,
+    /// Open file descriptors
+    Fds,
+    /// Share of total memory
+    RamPercent,
+    User,
+    /// Single-letter process state, e.g. 'R' for running or 'Z' for zombie
+    Status,
+}
+
+impl Column {
+    pub(crate) fn all() -> impl Iterator<Item = Column> {
+        vec![
+            Column::Pid,
+            Column::Cpu,
+            Column::Ram,
+            Column::Uptime,
+            Column::Read,
+            Column::Write,
+            Column::Fds,
+            Column::RamPercent,
+            Column::User,
+            Column::Status,
+        ]
+        .into_iter()
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Column::Pid => "pid",
+            Column::Cpu => "cpu",
+            Column::Ram => "ram",
+            Column::Uptime => "uptime",
+            Column::Read => "read",
+            Column::Write => "write",
+            Column::Fds => "fds",
+            Column::RamPercent => "ram%",
+            Column::User => "user",
+            Column::Status => "s",
+        }
+    }
+
+    /// Width of the cells, not counting the space in front of them. The pid column can be wider,
+    /// see `Process::pid_width`.
+    fn width(self) -> usize {
+        match self {
+            Column::Pid => 7,
+            Column::Cpu | Column::Fds => 5,
+            Column::Ram => 9,
+            Column::Uptime | Column::Read | Column::Write | Column::RamPercent => 6,
+            Column::User => 8,
+            Column::Status => 1,
+        }
+    }
+
+    /// The sort column for this column, if it can be sorted by.
+    pub(crate) fn sort_by(self) -> Option<SortBy> {
+        match self {
+            Column::Pid => Some(SortBy::Pid),
+            Column::Cpu => Some(SortBy::Cpu),
+            Column::Ram => Some(SortBy::Ram),
+            Column::Uptime => Some(SortBy::Uptime),
+            Column::Read => Some(SortBy::DiskRead),
+            Column::Write => Some(SortBy::DiskWrite),
+            Column::Fds => Some(SortBy::Fds),
+            Column::RamPercent | Column::User | Column::Status => None,
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub(crate) enum SortBy {
     Pid,
     Cpu,
     Ram,
+    Uptime,
+    /// Bytes read from disk per second
+    DiskRead,
+    /// Bytes written to disk per second
+    DiskWrite,
+    /// Open file descriptors
+    Fds,
+    Name,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -177,16 +857,81 @@ impl Default for SortBy {
 }
 
 impl SortBy {
-    pub(crate) fn next(self) -> SortBy {
+    pub(crate) fn descending_by_default(self) -> bool {
         match self {
-            SortBy::Pid => SortBy::Cpu,
-            SortBy::Cpu => SortBy::Ram,
-            SortBy::Ram => SortBy::Pid,
+            SortBy::Pid | SortBy::Name => false,
+            SortBy::Cpu
+            | SortBy::Ram
+            | SortBy::Uptime
+            | SortBy::DiskRead
+            | SortBy::DiskWrite
+            | SortBy::Fds => true,
         }
     }
 
-    fn all() -> impl Iterator<Item = SortBy> {
-        vec![SortBy::Pid, SortBy::Cpu, SortBy::Ram].into_iter()
+    /// The next column in `enabled`, wrapping around. Returns `self` if nothing is enabled.
+    pub(crate) fn next(self, enabled: &[SortBy]) -> SortBy {
+        let mut column = self;
+        for _ in SortBy::all() {
+            column = match column {
+                SortBy::Pid => SortBy::Cpu,
+                SortBy::Cpu => SortBy::Ram,
+                SortBy::Ram => SortBy::Uptime,
+                SortBy::Uptime => SortBy::DiskRead,
+                SortBy::DiskRead => SortBy::DiskWrite,
+                SortBy::DiskWrite => SortBy::Fds,
+                SortBy::Fds => SortBy::Name,
+                SortBy::Name => SortBy::Pid,
+            };
+            if enabled.contains(&column) {
+                return column;
+            }
+        }
+        self
+    }
+
+    /// The previous column in `enabled`, wrapping around. Returns `self` if nothing is enabled.
+    pub(crate) fn prev(self, enabled: &[SortBy]) -> SortBy {
+        let mut column = self;
+        for _ in SortBy::all() {
+            column = match column {
+                SortBy::Pid => SortBy::Name,
+                SortBy::Cpu => SortBy::Pid,
+                SortBy::Ram => SortBy::Cpu,
+                SortBy::Uptime => SortBy::Ram,
+                SortBy::DiskRead => SortBy::Uptime,
+                SortBy::DiskWrite => SortBy::DiskRead,
+                SortBy::Fds => SortBy::DiskWrite,
+                SortBy::Name => SortBy::Fds,
+            };
+            if enabled.contains(&column) {
+                return column;
+            }
+        }
+        self
+    }
+
+    /// The default column if it's enabled, otherwise the first enabled one.
+    pub(crate) fn initial(enabled: &[SortBy]) -> SortBy {
+        if enabled.contains(&SortBy::default()) {
+            SortBy::default()
+        } else {
+            SortBy::default().next(enabled)
+        }
+    }
+
+    pub(crate) fn all() -> impl Iterator<Item = SortBy> {
+        vec![
+            SortBy::Pid,
+            SortBy::Cpu,
+            SortBy::Ram,
+            SortBy::Uptime,
+            SortBy::DiskRead,
+            SortBy::DiskWrite,
+            SortBy::Fds,
+            SortBy::Name,
+        ]
+        .into_iter()
     }
 }
 
@@ -198,53 +943,303 @@ pub(crate) struct ProcessWatcher(ProcessWatcherInner);
 enum ProcessWatcherInner {
     Production {
         system: sysinfo::System,
+        users: Users,
+        /// Total bytes read and written by every process, as of the last refresh
+        disk_totals: HashMap<Pid, (u64, u64)>,
+        /// Bytes read and written per second between the last two refreshes
+        disk_rates: HashMap<Pid, (u64, u64)>,
+        /// Open file descriptors of every process that they could be counted for
+        fds: HashMap<Pid, usize>,
+        last_refresh: Option<Instant>,
+        /// Whether working directories are fetched on every refresh, for '--search-cwd'
+        search_cwd: bool,
+    },
+    FromProcDir {
+        proc_dir: ProcDir,
+        processes: Vec<Process>,
+        total_memory: u64,
+        used_memory: u64,
     },
     #[cfg(test)]
     TestWatcher {
         processes: Vec<Process>,
+        total_memory: u64,
+        used_memory: u64,
+        global_cpu: f32,
     },
 }
 
 impl ProcessWatcher {
-    pub(crate) fn new(system: sysinfo::System) -> ProcessWatcher {
-        ProcessWatcher(ProcessWatcherInner::Production { system })
+    pub(crate) fn new(system: sysinfo::System, search_cwd: bool) -> ProcessWatcher {
+        ProcessWatcher(ProcessWatcherInner::Production {
+            system,
+            users: Users::new_with_refreshed_list(),
+            disk_totals: HashMap::new(),
+            disk_rates: HashMap::new(),
+            fds: HashMap::new(),
+            last_refresh: None,
+            search_cwd,
+        })
     }
 
-    pub(crate) fn refresh(&mut self) {
-        match self {
-            ProcessWatcher(ProcessWatcherInner::Production { system }) => system
-                .refresh_processes_specifics(
+    pub(crate) fn from_proc_dir(proc_dir: ProcDir) -> ProcessWatcher {
+        ProcessWatcher(ProcessWatcherInner::FromProcDir {
+            proc_dir,
+            processes: Vec::new(),
+            total_memory: 0,
+            used_memory: 0,
+        })
+    }
+
+    /// Takes a first sample and waits until the next `refresh` can compute cpu usages from the
+    /// difference to it. Snapshots from proc dirs have no cpu usage, so they're left alone.
+    pub(crate) fn warm_up(&mut self) {
+        if let ProcessWatcher(ProcessWatcherInner::Production { .. }) = self {
+            // failures are reported by the following refresh
+            self.refresh().ok();
+            std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+        }
+    }
+
+    /// Fails if no processes could be read, which can't happen on a working system, since at
+    /// least treetop itself is running.
+    pub(crate) fn refresh(&mut self) -> R<()> {
+        let found_processes = match self {
+            ProcessWatcher(ProcessWatcherInner::Production {
+                system,
+                disk_totals,
+                disk_rates,
+                fds,
+                last_refresh,
+                search_cwd,
+                ..
+            }) => {
+                system.refresh_memory();
+                system.refresh_cpu_usage();
+                system.refresh_processes_specifics(
                     ProcessRefreshKind::new()
                         .with_memory()
                         .with_cpu()
-                        .with_cmd(UpdateKind::OnlyIfNotSet),
-                ),
+                        .with_disk_usage()
+                        .with_cmd(UpdateKind::OnlyIfNotSet)
+                        .with_exe(UpdateKind::OnlyIfNotSet)
+                        .with_cwd(if *search_cwd {
+                            UpdateKind::Always
+                        } else {
+                            UpdateKind::Never
+                        }),
+                );
+                let now = Instant::now();
+                let seconds = last_refresh.map(|last_refresh| (now - last_refresh).as_secs_f64());
+                let totals: HashMap<Pid, (u64, u64)> = system
+                    .processes()
+                    .iter()
+                    .map(|(pid, process)| {
+                        let usage = process.disk_usage();
+                        (*pid, (usage.total_read_bytes, usage.total_written_bytes))
+                    })
+                    .collect();
+                *disk_rates = totals
+                    .iter()
+                    .filter_map(|(pid, (read, written))| {
+                        let seconds = seconds?;
+                        let (previous_read, previous_written) = disk_totals.get(pid)?;
+                        Some((
+                            *pid,
+                            (
+                                per_second(*previous_read, *read, seconds),
+                                per_second(*previous_written, *written, seconds),
+                            ),
+                        ))
+                    })
+                    .collect();
+                *disk_totals = totals;
+                *fds = system
+                    .processes()
+                    .keys()
+                    .filter_map(|pid| Some((*pid, count_fds(*pid)?)))
+                    .collect();
+                *last_refresh = Some(now);
+                !system.processes().is_empty()
+            }
+            ProcessWatcher(ProcessWatcherInner::FromProcDir {
+                proc_dir,
+                processes,
+                total_memory,
+                used_memory,
+            }) => {
+                *processes = proc_dir
+                    .processes()
+                    .into_iter()
+                    .map(|(stat, arguments)| {
+                        let uptime = proc_dir.process_uptime(&stat);
+                        Process::from_proc_dir_entry(&stat, arguments, uptime)
+                    })
+                    .collect();
+                *total_memory = proc_dir.total_memory();
+                *used_memory = proc_dir.used_memory();
+                !processes.is_empty()
+            }
+            #[cfg(test)]
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { processes, .. }) => {
+                !processes.is_empty()
+            }
+        };
+        if !found_processes {
+            Err("failed to read processes")?;
+        }
+        Ok(())
+    }
+
+    /// Userland threads are only included with `threads`.
+    fn processes(&self, threads: bool) -> Vec<Process> {
+        let keep = |thread: bool| threads || !thread;
+        match self {
+            ProcessWatcher(ProcessWatcherInner::Production {
+                system,
+                users,
+                disk_rates,
+                fds,
+                ..
+            }) => system
+                .processes()
+                .values()
+                // skipped before converting, as there can be many more threads than processes
+                .filter(|process| keep(process.thread_kind() == Some(ThreadKind::Userland)))
+                .map(|process| {
+                    let disk_rates = disk_rates.get(&process.pid()).copied();
+                    Process::from_sysinfo_process(
+                        process,
+                        users,
+                        disk_rates.unwrap_or_default(),
+                        fds.get(&process.pid()).copied(),
+                    )
+                })
+                .collect(),
+            ProcessWatcher(ProcessWatcherInner::FromProcDir { processes, .. }) => processes
+                .iter()
+                .filter(|process| keep(process.thread))
+                .cloned()
+                .collect(),
             #[cfg(test)]
-            ProcessWatcher(ProcessWatcherInner::TestWatcher { .. }) => {}
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { processes, .. }) => processes
+                .iter()
+                .filter(|process| keep(process.thread))
+                .cloned()
+                .collect(),
         }
     }
 
     pub(crate) fn get_forest(&self) -> Forest<Process> {
+        self.get_forest_with(false, false)
+    }
+
+    /// Like `get_forest`, optionally with userland threads below their processes, and with all
+    /// kernel threads below a synthetic '[kernel]' root.
+    pub(crate) fn get_forest_with(&self, threads: bool, group_kernel: bool) -> Forest<Process> {
+        let processes = self.processes(threads);
+        if group_kernel {
+            Forest::new_forest(Process::group_kernel_threads(processes).into_iter())
+        } else {
+            Forest::new_forest(processes.into_iter())
+        }
+    }
+
+    pub(crate) fn total_memory(&self) -> u64 {
         match self {
-            ProcessWatcher(ProcessWatcherInner::Production { system }) => Forest::new_forest(
-                system
-                    .processes()
-                    .values()
-                    .filter(|process| process.thread_kind() != Some(ThreadKind::Userland))
-                    .map(Process::from_sysinfo_process),
-            ),
+            ProcessWatcher(ProcessWatcherInner::Production { system, .. }) => system.total_memory(),
+            ProcessWatcher(ProcessWatcherInner::FromProcDir { total_memory, .. }) => *total_memory,
+            #[cfg(test)]
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { total_memory, .. }) => *total_memory,
+        }
+    }
+
+    pub(crate) fn used_memory(&self) -> u64 {
+        match self {
+            ProcessWatcher(ProcessWatcherInner::Production { system, .. }) => system.used_memory(),
+            ProcessWatcher(ProcessWatcherInner::FromProcDir { used_memory, .. }) => *used_memory,
             #[cfg(test)]
-            ProcessWatcher(ProcessWatcherInner::TestWatcher { processes }) => {
-                Forest::new_forest(processes.iter().cloned())
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { used_memory, .. }) => *used_memory,
+        }
+    }
+
+    /// The current working directory of the given process. Without '--search-cwd' they're not
+    /// fetched on refresh, so it's read on demand, e.g. for the details pane.
+    pub(crate) fn cwd(&self, pid: Pid) -> Option<PathBuf> {
+        match self {
+            ProcessWatcher(ProcessWatcherInner::Production { system, .. }) => system
+                .process(pid)
+                .and_then(sysinfo::Process::cwd)
+                .map(Path::to_path_buf)
+                .or_else(|| read_cwd(pid)),
+            ProcessWatcher(ProcessWatcherInner::FromProcDir { .. }) => None,
+            #[cfg(test)]
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { processes, .. }) => processes
+                .iter()
+                .find(|process| process.pid == pid)
+                .and_then(|process| process.cwd.clone()),
+        }
+    }
+
+    /// Cpu usage of the whole system in percent, averaged over all cores. Always 0 for captured
+    /// proc directories.
+    pub(crate) fn global_cpu(&self) -> f32 {
+        match self {
+            ProcessWatcher(ProcessWatcherInner::Production { system, .. }) => {
+                system.global_cpu_info().cpu_usage()
             }
+            ProcessWatcher(ProcessWatcherInner::FromProcDir { .. }) => 0.0,
+            #[cfg(test)]
+            ProcessWatcher(ProcessWatcherInner::TestWatcher { global_cpu, .. }) => *global_cpu,
         }
     }
 }
 
+#[cfg(target_os = "linux")]
+fn count_fds(pid: Pid) -> Option<usize> {
+    count_dir_entries(&Path::new("/proc").join(pid.to_string()).join("fd"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn count_fds(_pid: Pid) -> Option<usize> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn read_cwd(pid: Pid) -> Option<PathBuf> {
+    std::fs::read_link(Path::new("/proc").join(pid.to_string()).join("cwd")).ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_cwd(_pid: Pid) -> Option<PathBuf> {
+    None
+}
+
+/// `None` if the directory can't be read.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn count_dir_entries(dir: &Path) -> Option<usize> {
+    Some(std::fs::read_dir(dir).ok()?.count())
+}
+
+/// The rate of change of an ever-increasing counter. 0 if the counter was reset.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn per_second(previous: u64, current: u64, seconds: f64) -> u64 {
+    if seconds <= 0.0 {
+        0
+    } else {
+        (current.saturating_sub(previous) as f64 / seconds) as u64
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
     use crate::R;
+    use ratatui::style::Color;
     use std::string::ToString;
 
     impl Process {
@@ -253,12 +1248,87 @@ pub(crate) mod test {
                 pid: pid.into(),
                 name: crate::utils::test::render_number(pid).to_string(),
                 arguments: Vec::new(),
+                exe: None,
+                show_full_path: false,
+                cwd: None,
                 parent: parent.map(From::from),
+                parent_name: None,
                 cpu,
                 ram: 0,
+                own_cpu: cpu,
+                own_ram: 0,
+                disk_read: 0,
+                disk_write: 0,
+                own_disk_read: 0,
+                own_disk_write: 0,
+                fds: None,
+                uptime: 0,
+                user: None,
+                status: ProcessStatus::Run,
+                kernel_thread: false,
+                thread: false,
             }
         }
 
+        pub(crate) fn set_thread(mut self) -> Self {
+            self.thread = true;
+            self
+        }
+
+        pub(crate) fn set_kernel_thread(mut self) -> Self {
+            self.kernel_thread = true;
+            self
+        }
+
+        pub(crate) fn set_name(mut self, name: &str) -> Self {
+            self.name = name.to_string();
+            self
+        }
+
+        pub(crate) fn set_exe(mut self, exe: &str) -> Self {
+            self.exe = Some(exe.to_string());
+            self
+        }
+
+        pub(crate) fn set_ram(mut self, ram: u64) -> Self {
+            self.ram = ram;
+            self.own_ram = ram;
+            self
+        }
+
+        pub(crate) fn set_disk_rates(mut self, read: u64, write: u64) -> Self {
+            self.disk_read = read;
+            self.disk_write = write;
+            self.own_disk_read = read;
+            self.own_disk_write = write;
+            self
+        }
+
+        pub(crate) fn set_fds(mut self, fds: usize) -> Self {
+            self.fds = Some(fds);
+            self
+        }
+
+        pub(crate) fn set_uptime(mut self, uptime: u64) -> Self {
+            self.uptime = uptime;
+            self
+        }
+
+        pub(crate) fn set_status(mut self, status: ProcessStatus) -> Self {
+            self.status = status;
+            self
+        }
+
+        pub(crate) fn set_cwd(mut self, cwd: &str) -> Self {
+            self.cwd = Some(PathBuf::from(cwd));
+            self
+        }
+
+        pub(crate) fn set_user(mut self, user: &str) -> Self {
+            self.user = Some(user.to_string());
+            self
+        }
+
         pub(crate) fn set_arguments(mut self, arguments: Vec<&str>) -> Self {
             self.arguments = arguments.into_iter().map(ToString::to_string).collect();
             self
@@ -271,16 +1341,60 @@ pub(crate) mod test {
                 pid: 42.into(),
                 name: "name".to_string(),
                 arguments: vec![],
+                exe: None,
+                show_full_path: false,
+                cwd: None,
                 parent: None,
+                parent_name: None,
                 cpu: 0.0,
                 ram: 0,
+                own_cpu: 0.0,
+                own_ram: 0,
+                disk_read: 0,
+                disk_write: 0,
+                own_disk_read: 0,
+                own_disk_write: 0,
+                fds: None,
+                uptime: 0,
+                user: None,
+                status: ProcessStatus::Run,
+                kernel_thread: false,
+                thread: false,
             }
         }
     }
 
     impl ProcessWatcher {
         pub(crate) fn fake(processes: Vec<Process>) -> ProcessWatcher {
-            ProcessWatcher(ProcessWatcherInner::TestWatcher { processes })
+            ProcessWatcher(ProcessWatcherInner::TestWatcher {
+                processes,
+                total_memory: 16 * 2_u64.pow(30),
+                used_memory: 0,
+                global_cpu: 0.0,
+            })
+        }
+
+        pub(crate) fn set_total_memory(mut self, total: u64) -> Self {
+            if let ProcessWatcher(ProcessWatcherInner::TestWatcher { total_memory, .. }) = &mut self
+            {
+                *total_memory = total;
+            }
+            self
+        }
+
+        pub(crate) fn set_used_memory(mut self, used: u64) -> Self {
+            if let ProcessWatcher(ProcessWatcherInner::TestWatcher { used_memory, .. }) = &mut self
+            {
+                *used_memory = used;
+            }
+            self
+        }
+
+        pub(crate) fn set_global_cpu(mut self, cpu: f32) -> Self {
+            if let ProcessWatcher(ProcessWatcherInner::TestWatcher { global_cpu, .. }) = &mut self {
+                *global_cpu = cpu;
+            }
+            self
         }
     }
 
@@ -316,6 +1430,81 @@ pub(crate) mod test {
             Ok(())
         }
 
+        #[test]
+        fn is_match_can_join_arguments_without_separator() -> R<()> {
+            let process = Process::default().set_arguments(vec!["foo", "bar"]);
+            let no_separator = Args {
+                argument_separator: ArgumentSeparator::None,
+                ..Args::default()
+            };
+            assert!(process.is_match(&Regex::new("foo bar")?, 0.into(), &Args::default()));
+            assert!(!process.is_match(&Regex::new("foobar")?, 0.into(), &Args::default()));
+            assert!(!process.is_match(&Regex::new("foo bar")?, 0.into(), &no_separator));
+            assert!(process.is_match(&Regex::new("foobar")?, 0.into(), &no_separator));
+            Ok(())
+        }
+
+        #[test]
+        fn is_match_can_join_arguments_with_newlines() -> R<()> {
+            let process = Process::default().set_arguments(vec!["foo", "bar"]);
+            let args = Args {
+                argument_separator: ArgumentSeparator::Newline,
+                ..Args::default()
+            };
+            assert!(process.is_match(&Regex::new("(?m)^bar$")?, 0.into(), &args));
+            assert!(!process.is_match(&Regex::new("o.b")?, 0.into(), &args));
+            Ok(())
+        }
+
+        #[test]
+        fn is_match_considers_the_user_when_asked_to() -> R<()> {
+            let process = Process::default().set_user("alice");
+            assert!(!process.is_match(&Regex::new("alice")?, 0.into(), &Args::default()));
+            assert!(process.is_match(
+                &Regex::new("alice")?,
+                0.into(),
+                &Args {
+                    match_user: true,
+                    ..Args::default()
+                }
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn is_match_can_match_the_working_directory() -> R<()> {
+            let process = Process {
+                cwd: Some(PathBuf::from("/srv/workers/billing")),
+                ..Process::default()
+            };
+            let search_cwd = Args {
+                search_cwd: true,
+                ..Args::default()
+            };
+            assert!(!process.is_match(&Regex::new("billing")?, 0.into(), &Args::default()));
+            assert!(process.is_match(&Regex::new("billing")?, 0.into(), &search_cwd));
+            assert!(!process.is_match(&Regex::new("shipping")?, 0.into(), &search_cwd));
+            Ok(())
+        }
+
+        #[test]
+        fn is_match_compares_pids_with_pid_ranges() -> R<()> {
+            let process = |pid: usize| Process {
+                pid: pid.into(),
+                name: "1500".to_string(),
+                ..Process::default()
+            };
+            let range = Regex::new("pid:1000-2000")?;
+            assert!(process(1000).is_match(&range, 0.into(), &Args::default()));
+            assert!(process(2000).is_match(&range, 0.into(), &Args::default()));
+            assert!(!process(999).is_match(&range, 0.into(), &Args::default()));
+            assert!(!process(2001).is_match(&range, 0.into(), &Args::default()));
+            let exact = Regex::new("pid:1234")?;
+            assert!(process(1234).is_match(&exact, 0.into(), &Args::default()));
+            assert!(!process(1235).is_match(&exact, 0.into(), &Args::default()));
+            Ok(())
+        }
+
         #[test]
         fn is_match_hides_treetop_for_arguments() -> R<()> {
             let process = Process {
@@ -350,4 +1539,247 @@ pub(crate) mod test {
             Ok(())
         }
     }
+
+    #[test]
+    fn colors_the_cpu_column_by_usage() {
+        let cpu_cells: Vec<(String, Option<Color>)> = [10.0, 50.0, 90.0]
+            .into_iter()
+            .map(|cpu| {
+                let span =
+                    Process::fake(1, cpu, None).table_data(&[Column::Cpu], 7, 0, Theme::Default)[1]
+                        .clone();
+                (span.content.to_string(), span.style.fg)
+            })
+            .collect();
+        assert_eq!(
+            cpu_cells,
+            vec![
+                ("  10%".to_string(), Some(Color::Green)),
+                ("  50%".to_string(), Some(Color::Yellow)),
+                ("  90%".to_string(), Some(Color::Red)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stable_sorting_ignores_small_cpu_differences() {
+        let order = |cpu_a, cpu_b, stable| {
+            Process::fake(1, cpu_a, None).compare(
+                &Process::fake(2, cpu_b, None),
+                SortBy::Cpu,
+                true,
+                stable,
+            )
+        };
+        assert_ne!(order(50.1, 50.4, false), order(50.4, 50.1, false));
+        assert_eq!(order(50.1, 50.4, true), order(50.4, 50.1, true));
+        assert_eq!(order(49.0, 50.4, true), std::cmp::Ordering::Greater);
+    }
+
+    #[test]
+    fn colors_the_ram_column_by_share_of_total_memory() {
+        let total_memory = 16 * 2_u64.pow(30);
+        let ram_cells: Vec<(String, Option<Color>)> = [1, 3, 8]
+            .into_iter()
+            .map(|gigabytes| {
+                let process = Process::fake(1, 0.0, None).set_ram(gigabytes * 2_u64.pow(30));
+                let span =
+                    process.table_data(&[Column::Ram], 7, total_memory, Theme::Default)[1].clone();
+                (span.content.to_string(), span.style.fg)
+            })
+            .collect();
+        assert_eq!(
+            ram_cells,
+            vec![
+                ("  1,024MB".to_string(), Some(Color::Green)),
+                ("  3,072MB".to_string(), Some(Color::Yellow)),
+                ("  8,192MB".to_string(), Some(Color::Red)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pid_cells_grow_with_the_widest_pid() {
+        let processes = [
+            Process::fake(1, 0.0, None),
+            Process::fake(123_456_789, 0.0, None),
+        ];
+        assert_eq!(Process::pid_width(processes[..1].iter()), 7);
+        let pid_width = Process::pid_width(processes.iter());
+        assert_eq!(pid_width, 9);
+        let pid_cells: Vec<String> = processes
+            .iter()
+            .map(|p| p.table_data(&[Column::Pid], pid_width, 0, Theme::Default)[1].to_string())
+            .collect();
+        assert_eq!(pid_cells, vec!["        1", "123456789"]);
+    }
+
+    #[test]
+    fn formats_disk_rates_with_binary_units() {
+        assert_eq!(format_rate(0), "0B");
+        assert_eq!(format_rate(1023), "1023B");
+        assert_eq!(format_rate(1536), "1.5K");
+        assert_eq!(format_rate(12 * 2_u64.pow(20)), "12M");
+        assert_eq!(format_rate(3 * 2_u64.pow(30)), "3.0G");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn counts_open_file_descriptors() {
+        let fds = count_fds(Pid::from_u32(std::process::id()));
+        assert!(fds.is_some_and(|fds| fds >= 3), "{fds:?}");
+    }
+
+    #[test]
+    fn unreadable_file_descriptors_are_shown_as_a_dash() {
+        assert_eq!(count_dir_entries(Path::new("/does/not/exist")), None);
+        let fds_cell = |process: Process| {
+            let spans = process.table_data(&[Column::Fds], 7, 0, Theme::Default);
+            spans[1].content.trim().to_string()
+        };
+        assert_eq!(fds_cell(Process::fake(1, 0.0, None)), "-");
+        assert_eq!(fds_cell(Process::fake(1, 0.0, None).set_fds(12)), "12");
+    }
+
+    #[test]
+    fn computes_rates_from_counters() {
+        assert_eq!(per_second(1000, 3000, 2.0), 1000);
+        assert_eq!(per_second(3000, 1000, 2.0), 0);
+        assert_eq!(per_second(1000, 3000, 0.0), 0);
+    }
+
+    #[test]
+    fn formats_uptimes_with_the_two_most_significant_units() {
+        assert_eq!(format_uptime(45), "45s");
+        assert_eq!(format_uptime(12 * 60 + 5), "12m");
+        assert_eq!(format_uptime(3 * 3600 + 5 * 60), "3h05m");
+        assert_eq!(format_uptime(86400 + 3 * 3600 + 59), "1d03h");
+    }
+
+    mod collapse_key {
+        use super::*;
+
+        #[test]
+        fn ignores_the_pid() {
+            assert_eq!(
+                Process::fake(1, 0.0, None).set_name("sleep").collapse_key(),
+                Process::fake(2, 5.0, Some(1))
+                    .set_name("sleep")
+                    .collapse_key()
+            );
+        }
+
+        #[test]
+        fn depends_on_the_arguments() {
+            let key = |arguments| Process::default().set_arguments(arguments).collapse_key();
+            assert_ne!(key(vec!["sleep", "10"]), key(vec!["sleep", "20"]));
+            assert_ne!(key(vec!["sleep", "10"]), key(vec!["sleep 10"]));
+        }
+
+        #[test]
+        fn is_stable() {
+            assert_eq!(
+                Process::default().set_name("sleep").collapse_key(),
+                0x6fd1_ee52_ff4e_9a58
+            );
+        }
+    }
+
+    #[test]
+    fn warming_up_takes_a_sample_before_the_first_refresh() -> R<()> {
+        let mut process_watcher = ProcessWatcher::new(sysinfo::System::new(), false);
+        process_watcher.warm_up();
+        process_watcher.refresh()?;
+        let ProcessWatcher(ProcessWatcherInner::Production { disk_rates, .. }) = &process_watcher
+        else {
+            panic!("expected a production watcher: {process_watcher:?}");
+        };
+        // rates are only computed from two samples
+        assert!(disk_rates.contains_key(&Pid::from_u32(std::process::id())));
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn file_descriptors_are_counted_on_refresh() -> R<()> {
+        let mut process_watcher = ProcessWatcher::new(sysinfo::System::new(), false);
+        process_watcher.refresh()?;
+        let own = process_watcher
+            .processes(false)
+            .into_iter()
+            .find(|process| process.pid == Pid::from_u32(std::process::id()));
+        assert!(own.is_some_and(|own| own.fds.is_some_and(|fds| fds >= 3)));
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn working_directories_are_only_fetched_for_search_cwd() -> R<()> {
+        let own_pid = Pid::from_u32(std::process::id());
+        let own_cwd = |process_watcher: &ProcessWatcher| {
+            process_watcher
+                .processes(false)
+                .into_iter()
+                .find(|process| process.pid == own_pid)
+                .and_then(|process| process.cwd)
+        };
+        let mut process_watcher = ProcessWatcher::new(sysinfo::System::new(), false);
+        process_watcher.refresh()?;
+        assert_eq!(own_cwd(&process_watcher), None);
+        assert_eq!(process_watcher.cwd(own_pid), Some(std::env::current_dir()?));
+        let mut process_watcher = ProcessWatcher::new(sysinfo::System::new(), true);
+        process_watcher.refresh()?;
+        assert_eq!(own_cwd(&process_watcher), Some(std::env::current_dir()?));
+        Ok(())
+    }
+
+    #[test]
+    fn warming_up_leaves_fake_watchers_alone() {
+        let mut process_watcher = ProcessWatcher::fake(vec![Process::fake(1, 5.0, None)]);
+        process_watcher.warm_up();
+        assert_eq!(
+            process_watcher
+                .processes(false)
+                .iter()
+                .map(Process::cpu)
+                .collect::<Vec<_>>(),
+            vec![5.0]
+        );
+    }
+
+    mod from_proc_dir {
+        use super::*;
+        use crate::proc_dir::test::stat;
+        use crate::tree::TreeStyle;
+
+        #[test]
+        fn builds_the_forest_from_a_proc_dir() -> R<()> {
+            let mut watcher = ProcessWatcher::from_proc_dir(ProcDir::fake(&[
+                ("1/stat", &stat(1, "init", 0)),
+                ("1/cmdline", "/sbin/init\0"),
+                ("20/stat", &stat(20, "bash", 1)),
+                ("20/cmdline", "-bash\0"),
+                ("300/stat", &stat(300, "sleep", 20)),
+                ("300/cmdline", "sleep\x0010\0"),
+                ("self/stat", "garbage"),
+                ("meminfo", "MemTotal: 1024 kB\n"),
+            ]));
+            watcher.refresh()?;
+            let forest = watcher.get_forest();
+            assert_eq!(
+                forest
+                    .render_forest_prefixes(TreeStyle::default())
+                    .into_iter()
+                    .map(|(prefix, process, _)| format!("{prefix}{process}"))
+                    .collect::<Vec<_>>(),
+                vec!["init", "└─┬ -bash", "  └── sleep 10"]
+            );
+            assert_eq!(
+                forest.roots().map(Process::ram).sum::<u64>(),
+                3 * 256 * 4096
+            );
+            assert_eq!(watcher.total_memory(), 2_u64.pow(20));
+            Ok(())
+        }
+    }
 }
@@ -1,9 +1,60 @@
 use crate::R;
+use std::convert::Infallible;
+use std::ops::{Range, RangeInclusive};
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum Regex {
-    Regex { regex: regex::Regex },
-    Invalid { regex: String },
+    Regex {
+        regex: regex::Regex,
+    },
+    /// Matches `needle` as a fixed string, ignoring regex metacharacters.
+    Literal {
+        needle: String,
+        regex: regex::Regex,
+    },
+    /// A pattern with a leading '!', for hiding the processes matched by `inner`.
+    Negated {
+        pattern: String,
+        inner: Box<Regex>,
+    },
+    /// Comma-separated terms, matching processes that match any of them. Each term is compiled
+    /// on its own, so one invalid term doesn't affect the others.
+    Multi {
+        pattern: String,
+        terms: Vec<Regex>,
+    },
+    /// A pattern with a leading 'parent:', for showing the processes whose parent's name matches
+    /// `inner`. See `Process::is_match`.
+    Parent {
+        pattern: String,
+        inner: Box<Regex>,
+    },
+    /// A pattern with a leading 'user:', for showing the processes owned by users whose name
+    /// matches `inner`. See `Process::is_match`.
+    User {
+        pattern: String,
+        inner: Box<Regex>,
+    },
+    /// A pattern like 'pid:1000-2000' or 'pid:1234', for showing the processes whose pid is in
+    /// `range` (inclusive). See `Process::is_match`.
+    PidRange {
+        pattern: String,
+        range: RangeInclusive<u32>,
+    },
+    /// Matches when the characters of `needle` appear in order, e.g. 'frfx' matches 'firefox'.
+    Fuzzy {
+        needle: String,
+    },
+    /// Only matches whole words, by surrounding `pattern` (or every one of its terms) with '\b'.
+    WholeWords {
+        pattern: String,
+        literal: bool,
+        inner: Box<Regex>,
+    },
+    Invalid {
+        regex: String,
+    },
 }
 
 impl Regex {
@@ -12,21 +63,332 @@ impl Regex {
     }
 
     pub(crate) fn new(regex: &str) -> R<Regex> {
+        if let Some(prefixed) = Regex::prefixed(regex, Regex::new) {
+            return prefixed;
+        }
+        if split_terms(regex).len() > 1 {
+            return Ok(Regex::from_string(regex.to_string(), false, false));
+        }
         Ok(Regex::Regex {
-            regex: ::regex::Regex::new(regex)?,
+            regex: compile(regex)?,
+        })
+    }
+
+    pub(crate) fn literal(needle: String) -> Regex {
+        let regex =
+            compile(&regex::escape(&needle)).expect("escaped literals should be valid regexes");
+        Regex::Literal { needle, regex }
+    }
+
+    pub(crate) fn is_literal(&self) -> bool {
+        match self {
+            Regex::Literal { .. } => true,
+            Regex::WholeWords { literal, .. } => *literal,
+            Regex::Negated { inner, .. }
+            | Regex::Parent { inner, .. }
+            | Regex::User { inner, .. } => inner.is_literal(),
+            Regex::Regex { .. }
+            | Regex::Multi { .. }
+            | Regex::PidRange { .. }
+            | Regex::Fuzzy { .. }
+            | Regex::Invalid { .. } => false,
+        }
+    }
+
+    pub(crate) fn is_fuzzy(&self) -> bool {
+        match self {
+            Regex::Fuzzy { .. } => true,
+            Regex::Negated { inner, .. }
+            | Regex::Parent { inner, .. }
+            | Regex::User { inner, .. } => inner.is_fuzzy(),
+            Regex::Regex { .. }
+            | Regex::Literal { .. }
+            | Regex::Multi { .. }
+            | Regex::WholeWords { .. }
+            | Regex::PidRange { .. }
+            | Regex::Invalid { .. } => false,
+        }
+    }
+
+    /// Whether this pattern (or one of its comma-separated terms) failed to compile.
+    pub(crate) fn is_invalid(&self) -> bool {
+        match self {
+            Regex::Invalid { .. } => true,
+            Regex::Negated { inner, .. }
+            | Regex::Parent { inner, .. }
+            | Regex::User { inner, .. }
+            | Regex::WholeWords { inner, .. } => inner.is_invalid(),
+            Regex::Multi { terms, .. } => terms.iter().any(Regex::is_invalid),
+            Regex::Regex { .. }
+            | Regex::Literal { .. }
+            | Regex::PidRange { .. }
+            | Regex::Fuzzy { .. } => false,
+        }
+    }
+
+    /// Whether nothing can match, because this pattern (or every one of its comma-separated
+    /// terms) failed to compile. Unlike `is_invalid`, a single invalid term doesn't count.
+    pub(crate) fn is_entirely_invalid(&self) -> bool {
+        match self {
+            Regex::Multi { terms, .. } => {
+                !terms.is_empty() && terms.iter().all(Regex::is_entirely_invalid)
+            }
+            Regex::Negated { inner, .. }
+            | Regex::Parent { inner, .. }
+            | Regex::User { inner, .. }
+            | Regex::WholeWords { inner, .. } => inner.is_entirely_invalid(),
+            _ => self.is_invalid(),
+        }
+    }
+
+    pub(crate) fn is_whole_words(&self) -> bool {
+        match self {
+            Regex::WholeWords { .. } => true,
+            Regex::Negated { inner, .. }
+            | Regex::Parent { inner, .. }
+            | Regex::User { inner, .. } => inner.is_whole_words(),
+            Regex::Regex { .. }
+            | Regex::Literal { .. }
+            | Regex::Multi { .. }
+            | Regex::PidRange { .. }
+            | Regex::Fuzzy { .. }
+            | Regex::Invalid { .. } => false,
+        }
+    }
+
+    /// Whether processes matching this pattern should be hidden instead of shown.
+    pub(crate) fn is_negated(&self) -> bool {
+        matches!(self, Regex::Negated { .. })
+    }
+
+    /// For 'parent:' patterns (possibly negated) the pattern that parent names have to match.
+    pub(crate) fn parent_pattern(&self) -> Option<&Regex> {
+        match self {
+            Regex::Parent { inner, .. } => Some(inner),
+            Regex::Negated { inner, .. } => inner.parent_pattern(),
+            _ => None,
+        }
+    }
+
+    /// Whether this pattern or one of its comma-separated terms is a 'parent:' pattern, which
+    /// requires parent names to be resolved.
+    pub(crate) fn uses_parent_names(&self) -> bool {
+        match self {
+            Regex::Parent { .. } => true,
+            Regex::Negated { inner, .. } => inner.uses_parent_names(),
+            Regex::Multi { terms, .. } => terms.iter().any(Regex::uses_parent_names),
+            _ => false,
+        }
+    }
+
+    /// For comma-separated patterns (possibly negated) the terms, any of which has to match.
+    pub(crate) fn terms(&self) -> Option<&[Regex]> {
+        match self {
+            Regex::Multi { terms, .. } => Some(terms),
+            Regex::Negated { inner, .. } => inner.terms(),
+            _ => None,
+        }
+    }
+
+    /// For 'user:' patterns (possibly negated) the pattern that user names have to match.
+    pub(crate) fn user_pattern(&self) -> Option<&Regex> {
+        match self {
+            Regex::User { inner, .. } => Some(inner),
+            Regex::Negated { inner, .. } => inner.user_pattern(),
+            _ => None,
+        }
+    }
+
+    /// For 'pid:' patterns (possibly negated) the range of pids that match.
+    pub(crate) fn pid_range(&self) -> Option<&RangeInclusive<u32>> {
+        match self {
+            Regex::PidRange { range, .. } => Some(range),
+            Regex::Negated { inner, .. } => inner.pid_range(),
+            _ => None,
+        }
+    }
+
+    /// Handles the '!', 'parent:', 'user:' and 'pid:' prefixes, building the part after the
+    /// prefix with `inner`. Returns `None` for patterns without any of them.
+    fn prefixed<E>(
+        pattern: &str,
+        inner: impl FnOnce(&str) -> Result<Regex, E>,
+    ) -> Option<Result<Regex, E>> {
+        let wrap = |rest: &str, variant: fn(String, Box<Regex>) -> Regex| {
+            Some(inner(rest).map(|inner| variant(pattern.to_string(), Box::new(inner))))
+        };
+        if let Some(rest) = pattern.strip_prefix('!') {
+            return wrap(rest, |pattern, inner| Regex::Negated { pattern, inner });
+        }
+        if let Some(rest) = pattern.strip_prefix(PARENT_PREFIX) {
+            return wrap(rest, |pattern, inner| Regex::Parent { pattern, inner });
+        }
+        if let Some(rest) = pattern.strip_prefix(USER_PREFIX) {
+            return wrap(rest, |pattern, inner| Regex::User { pattern, inner });
+        }
+        Regex::pid_range_from(pattern).map(Ok)
+    }
+
+    /// Parses 'pid:<low>-<high>' and 'pid:<pid>'. Incomplete ranges (e.g. while typing) and
+    /// empty ones (with `low` above `high`) are invalid. Returns `None` for patterns without the
+    /// prefix.
+    fn pid_range_from(pattern: &str) -> Option<Regex> {
+        let range = pattern.strip_prefix(PID_PREFIX)?;
+        let parse = |pid: &str| pid.trim().parse::<u32>().ok();
+        let range = match range.split_once('-') {
+            Some((low, high)) => parse(low)
+                .zip(parse(high))
+                .filter(|(low, high)| low <= high)
+                .map(|(low, high)| low..=high),
+            None => parse(range).map(|pid| pid..=pid),
+        };
+        Some(match range {
+            Some(range) => Regex::PidRange {
+                pattern: pattern.to_string(),
+                range,
+            },
+            None => Regex::Invalid {
+                regex: pattern.to_string(),
+            },
         })
     }
 
+    /// Switches between regex and literal matching, keeping the typed pattern.
+    pub(crate) fn toggle_literal(&mut self) {
+        *self = Regex::from_string(
+            self.as_str().to_string(),
+            !self.is_literal(),
+            self.is_whole_words(),
+        );
+    }
+
+    /// Switches between regex and fuzzy matching, keeping the typed pattern. Fuzzy matching
+    /// replaces literal and whole word matching.
+    pub(crate) fn toggle_fuzzy(&mut self) {
+        let pattern = self.as_str().to_string();
+        *self = if self.is_fuzzy() {
+            Regex::from_string(pattern, false, false)
+        } else {
+            Regex::fuzzy(pattern)
+        };
+    }
+
+    /// Like `Regex::from_string`, but for fuzzy matching. '!', 'parent:' and 'user:' prefixes
+    /// keep their meaning.
+    fn fuzzy(pattern: String) -> Regex {
+        let inner = |rest: &str| Ok::<_, Infallible>(Regex::fuzzy(rest.to_string()));
+        if let Some(Ok(prefixed)) = Regex::prefixed(&pattern, inner) {
+            return prefixed;
+        }
+        Regex::Fuzzy { needle: pattern }
+    }
+
+    /// Switches between matching anywhere and matching whole words only, keeping the typed
+    /// pattern.
+    pub(crate) fn toggle_whole_words(&mut self) {
+        *self = Regex::from_string(
+            self.as_str().to_string(),
+            self.is_literal(),
+            !self.is_whole_words(),
+        );
+    }
+
+    fn from_string(regex: String, literal: bool, whole_words: bool) -> Regex {
+        let inner = |rest: &str| {
+            Ok::<_, Infallible>(Regex::from_string(rest.to_string(), literal, whole_words))
+        };
+        if let Some(Ok(prefixed)) = Regex::prefixed(&regex, inner) {
+            return prefixed;
+        }
+        if whole_words {
+            let inner = Box::new(Regex::from_string(
+                word_bounded(&regex, literal),
+                false,
+                false,
+            ));
+            return Regex::WholeWords {
+                pattern: regex,
+                literal,
+                inner,
+            };
+        }
+        if literal {
+            return Regex::literal(regex);
+        }
+        let terms = split_terms(&regex);
+        if terms.len() > 1 {
+            let terms = terms
+                .into_iter()
+                .filter(|term| !term.is_empty())
+                .map(|term| match term.strip_prefix('!') {
+                    // negation only applies to whole patterns
+                    Some(_) => Regex::Invalid {
+                        regex: term.to_string(),
+                    },
+                    None => Regex::from_string(term.to_string(), false, false),
+                })
+                .collect();
+            return Regex::Multi {
+                pattern: regex,
+                terms,
+            };
+        }
+        match compile(&regex) {
+            Ok(regex) => Regex::Regex { regex },
+            Err(_) => Regex::Invalid { regex },
+        }
+    }
+
+    /// For negated patterns this tells whether `s` matches the part after the '!'. A lone '!'
+    /// doesn't match anything.
     pub(crate) fn is_match(&self, s: &str) -> bool {
         match self {
-            Regex::Regex { regex } => regex.is_match(s),
-            Regex::Invalid { .. } => false,
+            Regex::Regex { regex } | Regex::Literal { regex, .. } => regex.is_match(s),
+            Regex::Negated { inner, .. } => !inner.as_str().is_empty() && inner.is_match(s),
+            Regex::WholeWords { inner, .. } => inner.is_match(s),
+            Regex::Multi { terms, .. } => terms.iter().any(|term| term.is_match(s)),
+            Regex::Fuzzy { needle } => fuzzy_find(needle, s).is_some(),
+            Regex::Parent { .. }
+            | Regex::User { .. }
+            | Regex::PidRange { .. }
+            | Regex::Invalid { .. } => false,
+        }
+    }
+
+    /// Returns the byte ranges of all non-empty matches in `s`.
+    pub(crate) fn find_ranges(&self, s: &str) -> Vec<Range<usize>> {
+        match self {
+            Regex::Regex { regex } | Regex::Literal { regex, .. } => regex
+                .find_iter(s)
+                .map(|m| m.range())
+                .filter(|range| !range.is_empty())
+                .collect(),
+            Regex::WholeWords { inner, .. } => inner.find_ranges(s),
+            Regex::Multi { terms, .. } => {
+                let mut ranges: Vec<Range<usize>> =
+                    terms.iter().flat_map(|term| term.find_ranges(s)).collect();
+                ranges.sort_by_key(|range| range.start);
+                ranges
+            }
+            Regex::Fuzzy { needle } => fuzzy_find(needle, s).unwrap_or_default(),
+            Regex::Negated { .. }
+            | Regex::Parent { .. }
+            | Regex::User { .. }
+            | Regex::PidRange { .. }
+            | Regex::Invalid { .. } => Vec::new(),
         }
     }
 
     pub(crate) fn as_str(&self) -> &str {
         match self {
             Regex::Regex { regex } => regex.as_str(),
+            Regex::Literal { needle, .. } | Regex::Fuzzy { needle } => needle.as_str(),
+            Regex::Negated { pattern, .. }
+            | Regex::Parent { pattern, .. }
+            | Regex::User { pattern, .. }
+            | Regex::PidRange { pattern, .. }
+            | Regex::Multi { pattern, .. }
+            | Regex::WholeWords { pattern, .. } => pattern.as_str(),
             Regex::Invalid { regex } => regex.as_str(),
         }
     }
@@ -34,9 +396,470 @@ impl Regex {
     pub(crate) fn modify(&mut self, f: impl FnOnce(&mut String)) {
         let mut regex: String = self.as_str().to_string();
         f(&mut regex);
-        *self = match regex::Regex::new(&regex) {
-            Ok(regex) => Regex::Regex { regex },
-            Err(_) => Regex::Invalid { regex },
+        *self = self.with_pattern(regex);
+    }
+
+    /// A new pattern from the given text, matching in the same mode (e.g. literal or fuzzy).
+    pub(crate) fn with_pattern(&self, regex: String) -> Regex {
+        if self.is_fuzzy() {
+            Regex::fuzzy(regex)
+        } else {
+            Regex::from_string(regex, self.is_literal(), self.is_whole_words())
+        }
+    }
+}
+
+const PARENT_PREFIX: &str = "parent:";
+
+const USER_PREFIX: &str = "user:";
+
+const PID_PREFIX: &str = "pid:";
+
+fn word_bounded(regex: &str, literal: bool) -> String {
+    if literal {
+        return format!(r"\b(?:{})\b", regex::escape(regex));
+    }
+    split_terms(regex)
+        .into_iter()
+        .filter(|term| !term.is_empty())
+        .map(|term| format!(r"\b(?:{term})\b"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Splits `regex` at commas that are not escaped and not inside parentheses, brackets or
+/// braces, so that e.g. 'a{1,3}' stays a single term.
+fn split_terms(regex: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let mut depth: usize = 0;
+    let mut escaped = false;
+    let mut start = 0;
+    for (index, char) in regex.char_indices() {
+        match char {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                terms.push(&regex[start..index]);
+                start = index + 1;
+            }
+            _ => {}
         }
     }
+    terms.push(&regex[start..]);
+    terms
+}
+
+/// Finds the characters of `needle` in `haystack`, in order and as early as possible. Returns
+/// the byte ranges of the found characters, with adjacent characters merged into one range.
+/// Uses smart-case like `compile`.
+fn fuzzy_find(needle: &str, haystack: &str) -> Option<Vec<Range<usize>>> {
+    let case_sensitive = needle.chars().any(char::is_uppercase);
+    let chars_equal = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut haystack_chars = haystack.char_indices();
+    for needle_char in needle.chars() {
+        let (index, char) = haystack_chars.find(|(_, char)| chars_equal(*char, needle_char))?;
+        let end = index + char.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.end == index => last.end = end,
+            _ => ranges.push(index..end),
+        }
+    }
+    Some(ranges)
+}
+
+/// Compiles with smart-case: patterns without uppercase characters match case-insensitively.
+/// Escaped characters don't count, so e.g. '\S+' still matches case-insensitively.
+fn compile(regex: &str) -> Result<regex::Regex, regex::Error> {
+    regex::RegexBuilder::new(regex)
+        .case_insensitive(!has_unescaped_uppercase(regex))
+        .build()
+}
+
+fn has_unescaped_uppercase(regex: &str) -> bool {
+    let mut escaped = false;
+    regex.chars().any(|char| {
+        let is_uppercase = !escaped && char.is_uppercase();
+        escaped = !escaped && char == '\\';
+        is_uppercase
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lowercase_patterns_match_case_insensitively() -> R<()> {
+        assert!(Regex::new("ssh")?.is_match("SSH"));
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_with_uppercase_characters_match_case_sensitively() -> R<()> {
+        assert!(!Regex::new("SSH")?.is_match("ssh"));
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_uppercase_characters_do_not_make_patterns_case_sensitive() -> R<()> {
+        assert!(Regex::new(r"\S+")?.is_match("Foo"));
+        assert!(Regex::new(r"\Sfoo")?.is_match("xFOO"));
+        assert!(Regex::new(r"f\S+")?.is_match("FOO"));
+        assert!(!Regex::new(r"F\S+")?.is_match("foo"));
+        assert!(!Regex::new(r"\\F")?.is_match(r"\f"));
+        Ok(())
+    }
+
+    #[test]
+    fn finds_all_non_overlapping_matches() -> R<()> {
+        assert_eq!(
+            Regex::new("a")?.find_ranges("banana"),
+            vec![1..2, 3..4, 5..6]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn literals_ignore_regex_metacharacters() -> R<()> {
+        assert!(Regex::literal("a+b".to_string()).is_match("x/a+b/c"));
+        assert!(!Regex::new("a+b")?.is_match("x/a+b/c"));
+        assert_eq!(Regex::literal("a+b".to_string()).as_str(), "a+b");
+        Ok(())
+    }
+
+    #[test]
+    fn literals_can_contain_invalid_regexes() {
+        assert!(Regex::literal("foo(".to_string()).is_match("foo("));
+    }
+
+    #[test]
+    fn toggling_literal_mode_keeps_the_pattern() -> R<()> {
+        let mut regex = Regex::new("a.c")?;
+        regex.toggle_literal();
+        assert!(regex.is_literal());
+        assert!(!regex.is_match("abc"));
+        regex.modify(|regex| regex.push('d'));
+        assert!(regex.is_literal());
+        assert!(regex.is_match("a.cd"));
+        regex.toggle_literal();
+        assert!(!regex.is_literal());
+        assert!(regex.is_match("abcd"));
+        Ok(())
+    }
+
+    #[test]
+    fn leading_exclamation_marks_negate_patterns() -> R<()> {
+        let regex = Regex::new("!kworker")?;
+        assert!(regex.is_negated());
+        assert!(regex.is_match("kworker/0:1"));
+        assert_eq!(regex.as_str(), "!kworker");
+        assert!(regex.find_ranges("kworker/0:1").is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn parent_prefixes_are_kept_apart_from_the_pattern() -> R<()> {
+        let mut regex = Regex::new("parent:ss.d")?;
+        assert_eq!(regex.as_str(), "parent:ss.d");
+        assert_eq!(regex.parent_pattern().map(Regex::as_str), Some("ss.d"));
+        assert!(!regex.is_match("parent:sshd"));
+        regex.toggle_literal();
+        assert!(regex.is_literal());
+        assert_eq!(regex.parent_pattern().map(Regex::as_str), Some("ss.d"));
+        assert!(Regex::new("!parent:sshd")?.parent_pattern().is_some());
+        assert!(Regex::new("sshd")?.parent_pattern().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn user_prefixes_match_against_user_names() -> R<()> {
+        let mut regex = Regex::new("user:ro+t")?;
+        assert_eq!(regex.as_str(), "user:ro+t");
+        assert_eq!(regex.user_pattern().map(Regex::as_str), Some("ro+t"));
+        assert!(!regex.is_match("user:root"));
+        regex.toggle_literal();
+        assert!(regex.is_literal());
+        assert_eq!(regex.user_pattern().map(Regex::as_str), Some("ro+t"));
+        assert!(Regex::new("!user:root")?.user_pattern().is_some());
+        assert!(Regex::new("root")?.user_pattern().is_none());
+        assert!(Regex::fuzzy("user:rt".to_string())
+            .user_pattern()
+            .is_some_and(|user| user.is_match("root")));
+        Ok(())
+    }
+
+    #[test]
+    fn pid_prefixes_parse_inclusive_ranges() -> R<()> {
+        assert_eq!(
+            Regex::new("pid:1000-2000")?.pid_range(),
+            Some(&(1000..=2000))
+        );
+        assert_eq!(Regex::new("pid:1234")?.pid_range(), Some(&(1234..=1234)));
+        assert_eq!(Regex::new("!pid:1-9")?.pid_range(), Some(&(1..=9)));
+        assert_eq!(Regex::new("pid:1000-2000")?.as_str(), "pid:1000-2000");
+        assert!(Regex::new("1000")?.pid_range().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn terms_can_have_prefixes() -> R<()> {
+        let regex = Regex::new("nginx,parent:sshd,pid:1-10,user:root")?;
+        let terms = regex.terms().unwrap_or_default();
+        assert_eq!(terms.len(), 4);
+        assert_eq!(terms[1].parent_pattern().map(Regex::as_str), Some("sshd"));
+        assert_eq!(terms[2].pid_range(), Some(&(1..=10)));
+        assert_eq!(terms[3].user_pattern().map(Regex::as_str), Some("root"));
+        assert!(regex.uses_parent_names());
+        assert!(!Regex::new("nginx,sshd")?.uses_parent_names());
+        Ok(())
+    }
+
+    #[test]
+    fn negated_terms_are_invalid() -> R<()> {
+        let regex = Regex::new("nginx,!sshd")?;
+        assert!(regex.is_invalid());
+        assert!(!regex.is_entirely_invalid());
+        assert!(regex.is_match("nginx"));
+        assert!(!regex.is_match("sshd"));
+        Ok(())
+    }
+
+    #[test]
+    fn incomplete_pid_ranges_are_invalid() -> R<()> {
+        let mut regex = Regex::empty()?;
+        regex.modify(|regex| regex.push_str("pid:10-"));
+        assert!(regex.is_invalid());
+        assert!(regex.pid_range().is_none());
+        regex.modify(|regex| regex.push_str("20"));
+        assert_eq!(regex.pid_range(), Some(&(10..=20)));
+        regex.modify(|regex| *regex = "pid:x".to_string());
+        assert!(regex.is_invalid());
+        Ok(())
+    }
+
+    #[test]
+    fn reversed_pid_ranges_are_invalid() -> R<()> {
+        assert!(Regex::new("pid:2000-1000")?.is_invalid());
+        assert_eq!(
+            Regex::new("pid:1000-1000")?.pid_range(),
+            Some(&(1000..=1000))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn a_lone_exclamation_mark_does_not_match_anything() -> R<()> {
+        let mut regex = Regex::empty()?;
+        regex.modify(|regex| regex.push('!'));
+        assert!(regex.is_negated());
+        assert!(!regex.is_match("anything"));
+        Ok(())
+    }
+
+    #[test]
+    fn commas_separate_alternative_terms() -> R<()> {
+        let regex = Regex::new("foo,bar")?;
+        assert!(regex.is_match("foo"));
+        assert!(regex.is_match("bar"));
+        assert!(!regex.is_match("baz"));
+        assert_eq!(regex.as_str(), "foo,bar");
+        assert_eq!(regex.find_ranges("bar foo"), vec![0..3, 4..7]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_terms_do_not_affect_the_other_terms() -> R<()> {
+        let mut regex = Regex::empty()?;
+        regex.modify(|regex| regex.push_str("foo,("));
+        assert!(regex.is_match("foo"));
+        assert!(!regex.is_match("("));
+        let Regex::Multi { terms, .. } = &regex else {
+            panic!("expected multiple terms: {regex:?}");
+        };
+        assert!(matches!(terms[1], Regex::Invalid { .. }));
+        assert!(regex.is_invalid());
+        Ok(())
+    }
+
+    #[test]
+    fn empty_terms_are_ignored() -> R<()> {
+        let regex = Regex::new("foo,")?;
+        assert!(regex.is_match("foo"));
+        assert!(!regex.is_match("bar"));
+        Ok(())
+    }
+
+    #[test]
+    fn commas_inside_groups_and_repetitions_do_not_separate_terms() -> R<()> {
+        assert!(Regex::new("^a{1,2}$")?.is_match("aa"));
+        assert!(Regex::new("x[,]y")?.is_match("x,y"));
+        assert!(Regex::new(r"x\,y")?.is_match("x,y"));
+        Ok(())
+    }
+
+    #[test]
+    fn literals_are_not_split_at_commas() {
+        assert!(Regex::literal("a,b".to_string()).is_match("a,b"));
+    }
+
+    #[test]
+    fn unbalanced_patterns_are_invalid() -> R<()> {
+        let mut regex = Regex::empty()?;
+        regex.modify(|regex| regex.push_str("foo("));
+        assert!(regex.is_invalid());
+        regex.modify(|regex| regex.push(')'));
+        assert!(!regex.is_invalid());
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_are_entirely_invalid_only_if_all_terms_are() -> R<()> {
+        let regex = Regex::new("nginx,(")?;
+        assert!(regex.is_invalid());
+        assert!(!regex.is_entirely_invalid());
+        assert!(Regex::new("a),b)")?.is_entirely_invalid());
+        assert!(Regex::from_string("!(".to_string(), false, false).is_entirely_invalid());
+        assert!(!Regex::new("nginx")?.is_entirely_invalid());
+        Ok(())
+    }
+
+    mod whole_words {
+        use super::*;
+
+        fn whole_words(pattern: &str) -> Regex {
+            let mut regex = Regex::from_string(pattern.to_string(), false, false);
+            regex.toggle_whole_words();
+            regex
+        }
+
+        #[test]
+        fn only_matches_whole_words() {
+            let regex = whole_words("ss");
+            assert!(regex.is_match("use ss"));
+            assert!(!regex.is_match("assist"));
+            assert_eq!(regex.as_str(), "ss");
+            assert!(regex.is_whole_words());
+        }
+
+        #[test]
+        fn applies_to_every_term() {
+            let regex = whole_words("ss,vi");
+            assert!(regex.is_match("vi foo"));
+            assert!(regex.is_match("ss -t"));
+            assert!(!regex.is_match("vim assist"));
+        }
+
+        #[test]
+        fn survives_editing_and_invalid_patterns() {
+            let mut regex = whole_words("foo");
+            regex.modify(|regex| regex.push('('));
+            assert!(regex.is_invalid());
+            assert!(regex.is_whole_words());
+            regex.modify(|regex| regex.push(')'));
+            assert!(regex.is_match("a foo b"));
+            assert!(!regex.is_match("food"));
+        }
+
+        #[test]
+        fn combines_with_literals() {
+            let mut regex = whole_words("a.b");
+            regex.toggle_literal();
+            assert!(regex.is_literal());
+            assert!(regex.is_whole_words());
+            assert!(regex.is_match("x a.b y"));
+            assert!(!regex.is_match("x axb y"));
+            assert!(!regex.is_match("a.bc"));
+        }
+    }
+
+    mod fuzzy {
+        use super::*;
+
+        fn fuzzy(pattern: &str) -> Regex {
+            let mut regex = Regex::from_string(pattern.to_string(), false, false);
+            regex.toggle_fuzzy();
+            regex
+        }
+
+        #[test]
+        fn matches_subsequences() {
+            let regex = fuzzy("frfx");
+            assert!(regex.is_fuzzy());
+            assert!(regex.is_match("firefox"));
+            assert!(!regex.is_match("fxfr"));
+            assert!(!regex.is_match("firefly"));
+        }
+
+        #[test]
+        fn finds_the_matched_characters() {
+            assert_eq!(
+                fuzzy("frfx").find_ranges("/usr/bin/firefox"),
+                vec![9..10, 11..12, 13..14, 15..16]
+            );
+            assert_eq!(fuzzy("fire").find_ranges("firefox"), vec![0..4]);
+            assert!(fuzzy("xyz").find_ranges("firefox").is_empty());
+        }
+
+        #[test]
+        fn ranges_are_byte_ranges() {
+            assert_eq!(fuzzy("ab").find_ranges("äab"), vec![2..4]);
+            assert_eq!(fuzzy("äb").find_ranges("xäyb"), vec![1..3, 4..5]);
+        }
+
+        #[test]
+        fn uses_smart_case() {
+            assert!(fuzzy("ff").is_match("FireFox"));
+            assert!(fuzzy("FF").is_match("FireFox"));
+            assert!(!fuzzy("FF").is_match("firefox"));
+        }
+
+        #[test]
+        fn ignores_regex_metacharacters() {
+            assert!(fuzzy("a.c").is_match("xa.yc"));
+            assert!(!fuzzy("a.c").is_match("abc"));
+            assert!(!fuzzy("(").is_invalid());
+        }
+
+        #[test]
+        fn survives_editing_and_toggling_back() {
+            let mut regex = fuzzy("fr");
+            regex.modify(|regex| regex.push('x'));
+            assert!(regex.is_fuzzy());
+            assert!(regex.is_match("firefox"));
+            regex.toggle_fuzzy();
+            assert!(!regex.is_fuzzy());
+            assert_eq!(regex.as_str(), "frx");
+            assert!(!regex.is_match("firefox"));
+        }
+
+        #[test]
+        fn keeps_negation_and_parent_prefixes() {
+            let regex = fuzzy("!frfx");
+            assert!(regex.is_negated());
+            assert!(regex.is_fuzzy());
+            assert!(regex.is_match("firefox"));
+            let regex = fuzzy("parent:frfx");
+            assert!(regex
+                .parent_pattern()
+                .is_some_and(|parent| parent.is_match("firefox")));
+        }
+    }
+
+    #[test]
+    fn modify_keeps_the_typed_pattern() -> R<()> {
+        let mut regex = Regex::empty()?;
+        regex.modify(|regex| regex.push_str("ssh"));
+        assert!(regex.is_match("SSH"));
+        assert_eq!(regex.as_str(), "ssh");
+        Ok(())
+    }
 }
@@ -0,0 +1,53 @@
+use nix::sys::signal::Signal;
+use nix::unistd::Pid;
+use std::fmt::Debug;
+
+pub(crate) trait Signaler: Debug {
+    fn send(&self, pid: Pid, signal: Signal) -> nix::Result<()>;
+}
+
+#[derive(Debug)]
+pub(crate) struct KillSignaler;
+
+impl Signaler for KillSignaler {
+    fn send(&self, pid: Pid, signal: Signal) -> nix::Result<()> {
+        nix::sys::signal::kill(pid, signal)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use nix::errno::Errno;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct FakeSignaler {
+        pub(crate) sent: Rc<RefCell<Vec<(Pid, Signal)>>>,
+        error: Option<Errno>,
+    }
+
+    impl FakeSignaler {
+        pub(crate) fn fake() -> FakeSignaler {
+            FakeSignaler::default()
+        }
+
+        pub(crate) fn failing_with(error: Errno) -> FakeSignaler {
+            FakeSignaler {
+                error: Some(error),
+                ..FakeSignaler::default()
+            }
+        }
+    }
+
+    impl Signaler for FakeSignaler {
+        fn send(&self, pid: Pid, signal: Signal) -> nix::Result<()> {
+            self.sent.borrow_mut().push((pid, signal));
+            match self.error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::process::SortBy;
+use crate::R;
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+#[cfg(test)]
+use std::rc::Rc;
+
+/// Settings that are remembered between runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct State {
+    pub(crate) pattern: String,
+    pub(crate) sort_column: SortBy,
+    pub(crate) descending: bool,
+    /// `Process::collapse_key`s of the collapsed subtrees
+    #[serde(default)]
+    pub(crate) collapsed: BTreeSet<u64>,
+}
+
+#[derive(Debug)]
+pub(crate) struct StateFile(StateFileInner);
+
+#[derive(Debug)]
+enum StateFileInner {
+    Disabled,
+    Path(PathBuf),
+    #[cfg(test)]
+    Fake(Rc<RefCell<Option<String>>>),
+}
+
+impl StateFile {
+    /// `$XDG_CONFIG_HOME/treetop/state.json`, falling back to `~/.config`. Disabled if neither
+    /// is set.
+    pub(crate) fn new() -> StateFile {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+        match config_dir {
+            Some(config_dir) => {
+                StateFile(StateFileInner::Path(config_dir.join("treetop/state.json")))
+            }
+            None => StateFile::disabled(),
+        }
+    }
+
+    pub(crate) fn disabled() -> StateFile {
+        StateFile(StateFileInner::Disabled)
+    }
+
+    /// Returns `None` if there is no state file or it can't be parsed.
+    pub(crate) fn load(&self) -> Option<State> {
+        let contents = match self {
+            StateFile(StateFileInner::Disabled) => return None,
+            StateFile(StateFileInner::Path(path)) => fs::read_to_string(path).ok()?,
+            #[cfg(test)]
+            StateFile(StateFileInner::Fake(contents)) => contents.borrow().clone()?,
+        };
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub(crate) fn save(&self, state: &State) -> R<()> {
+        let contents = serde_json::to_string_pretty(state)?;
+        match self {
+            StateFile(StateFileInner::Disabled) => {}
+            StateFile(StateFileInner::Path(path)) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(path, contents)?;
+            }
+            #[cfg(test)]
+            StateFile(StateFileInner::Fake(file)) => *file.borrow_mut() = Some(contents),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+
+    impl StateFile {
+        pub(crate) fn fake(contents: Option<&str>) -> (StateFile, Rc<RefCell<Option<String>>>) {
+            let file = Rc::new(RefCell::new(contents.map(ToString::to_string)));
+            (StateFile(StateFileInner::Fake(Rc::clone(&file))), file)
+        }
+    }
+
+    #[test]
+    fn round_trips_the_state() -> R<()> {
+        let state = State {
+            pattern: "ssh".to_string(),
+            sort_column: SortBy::Uptime,
+            descending: false,
+            collapsed: BTreeSet::from([3, 1]),
+        };
+        let (state_file, _) = StateFile::fake(None);
+        state_file.save(&state)?;
+        assert_eq!(state_file.load(), Some(state));
+        Ok(())
+    }
+
+    #[test]
+    fn loads_state_files_without_collapsed_subtrees() {
+        let (state_file, _) = StateFile::fake(Some(
+            r#"{"pattern": "ssh", "sort_column": "ram", "descending": false}"#,
+        ));
+        assert_eq!(
+            state_file.load().map(|state| state.collapsed),
+            Some(BTreeSet::new())
+        );
+    }
+
+    #[test]
+    fn ignores_corrupt_state_files() {
+        let (state_file, _) = StateFile::fake(Some("{\"pattern\": "));
+        assert_eq!(state_file.load(), None);
+        let (state_file, _) = StateFile::fake(Some("{\"sort_column\": \"nonsense\"}"));
+        assert_eq!(state_file.load(), None);
+    }
+
+    #[test]
+    fn disabled_state_files_are_never_written() -> R<()> {
+        let state_file = StateFile::disabled();
+        state_file.save(&State {
+            pattern: "ssh".to_string(),
+            sort_column: SortBy::Pid,
+            descending: false,
+            collapsed: BTreeSet::new(),
+        })?;
+        assert_eq!(state_file.load(), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use ratatui::style::{Color, Style, Stylize};
+
+/// The colors used for the table and the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum Theme {
+    /// The terminal's own palette
+    #[default]
+    Default,
+    /// No colors, only text attributes like bold and underline
+    Mono,
+    /// The solarized palette, for terminals with true color support
+    Solarized,
+}
+
+/// How close a process's cpu or ram usage is to being a problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Usage {
+    Low,
+    Warning,
+    Critical,
+}
+
+mod solarized {
+    use ratatui::style::Color;
+
+    pub(super) const BASE01: Color = Color::Rgb(0x58, 0x6e, 0x75);
+    pub(super) const YELLOW: Color = Color::Rgb(0xb5, 0x89, 0x00);
+    pub(super) const ORANGE: Color = Color::Rgb(0xcb, 0x4b, 0x16);
+    pub(super) const RED: Color = Color::Rgb(0xdc, 0x32, 0x2f);
+    pub(super) const MAGENTA: Color = Color::Rgb(0xd3, 0x36, 0x82);
+    pub(super) const BLUE: Color = Color::Rgb(0x26, 0x8b, 0xd2);
+    pub(super) const CYAN: Color = Color::Rgb(0x2a, 0xa1, 0x98);
+    pub(super) const GREEN: Color = Color::Rgb(0x85, 0x99, 0x00);
+}
+
+impl Theme {
+    fn color(self, default: Color, solarized: Color) -> Style {
+        match self {
+            Theme::Default => Style::new().fg(default),
+            Theme::Mono => Style::new(),
+            Theme::Solarized => Style::new().fg(solarized),
+        }
+    }
+
+    /// Table separators and text of lesser importance, e.g. columns that can't be sorted by.
+    pub(crate) fn dimmed(self) -> Style {
+        match self {
+            Theme::Mono => Style::new().dim(),
+            Theme::Default | Theme::Solarized => self.color(Color::DarkGray, solarized::BASE01),
+        }
+    }
+
+    /// The lines drawing the tree.
+    pub(crate) fn tree_prefix(self) -> Style {
+        self.color(Color::Blue, solarized::BLUE)
+    }
+
+    /// The command of the process that signals are sent to.
+    pub(crate) fn selected_process(self) -> Style {
+        self.color(Color::Blue, solarized::BLUE).reversed()
+    }
+
+    /// Matches of the search pattern.
+    pub(crate) fn pattern_match(self) -> Style {
+        match self {
+            Theme::Mono => Style::new().bold().underlined(),
+            Theme::Default | Theme::Solarized => {
+                self.color(Color::Yellow, solarized::YELLOW).bold()
+            }
+        }
+    }
+
+    /// Matches of the secondary pattern given with '--highlight'.
+    pub(crate) fn secondary_match(self) -> Style {
+        match self {
+            Theme::Mono => Style::new().italic().underlined(),
+            Theme::Default | Theme::Solarized => {
+                self.color(Color::Magenta, solarized::MAGENTA).bold()
+            }
+        }
+    }
+
+    /// Cpu and ram cells, and process states like zombies (critical) or stopped (warning).
+    pub(crate) fn usage(self, usage: Usage) -> Style {
+        match (self, usage) {
+            (Theme::Mono, Usage::Low | Usage::Warning) => Style::new(),
+            (Theme::Mono, Usage::Critical) => Style::new().bold(),
+            (_, Usage::Low) => self.color(Color::Green, solarized::GREEN),
+            (_, Usage::Warning) => self.color(Color::Yellow, solarized::ORANGE),
+            (_, Usage::Critical) => self.color(Color::Red, solarized::RED),
+        }
+    }
+
+    /// The header of the column the tree is sorted by.
+    pub(crate) fn sort_column(self) -> Style {
+        match self {
+            Theme::Default | Theme::Mono => Style::new().reversed(),
+            Theme::Solarized => Style::new().fg(solarized::CYAN).reversed(),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::collections::VecDeque;
@@ -15,13 +16,115 @@ pub(crate) trait Node {
     fn accumulate_from(&mut self, other: &Self);
 }
 
-#[derive(Debug)]
+/// The set of box-drawing characters used to draw the tree and the table around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub(crate) enum TreeStyle {
+    /// Straight corners and heavy table separators
+    #[default]
+    Plain,
+    /// Rounded corners and light table separators
+    Rounded,
+    /// Only ASCII characters, for terminals and fonts without box-drawing characters
+    Ascii,
+}
+
+impl TreeStyle {
+    fn branch(self) -> &'static str {
+        match self {
+            TreeStyle::Plain | TreeStyle::Rounded => "├─",
+            TreeStyle::Ascii => "|-",
+        }
+    }
+
+    fn last_branch(self) -> &'static str {
+        match self {
+            TreeStyle::Plain => "└─",
+            TreeStyle::Rounded => "╰─",
+            TreeStyle::Ascii => "`-",
+        }
+    }
+
+    fn vertical(self) -> &'static str {
+        match self {
+            TreeStyle::Plain | TreeStyle::Rounded => "│ ",
+            TreeStyle::Ascii => "| ",
+        }
+    }
+
+    fn leaf(self) -> &'static str {
+        match self {
+            TreeStyle::Plain | TreeStyle::Rounded => "─ ",
+            TreeStyle::Ascii => "- ",
+        }
+    }
+
+    fn fork(self) -> &'static str {
+        match self {
+            TreeStyle::Plain => "┬ ",
+            TreeStyle::Rounded => "╮ ",
+            TreeStyle::Ascii => "+ ",
+        }
+    }
+
+    /// Marks collapsed subtrees. ASCII uses '+' for forks already.
+    fn collapsed(self) -> &'static str {
+        match self {
+            TreeStyle::Plain | TreeStyle::Rounded => "+ ",
+            TreeStyle::Ascii => "> ",
+        }
+    }
+
+    pub(crate) fn column_separator(self) -> &'static str {
+        match self {
+            TreeStyle::Plain => "┃",
+            TreeStyle::Rounded => "│",
+            TreeStyle::Ascii => "|",
+        }
+    }
+
+    pub(crate) fn header_rule(self) -> &'static str {
+        match self {
+            TreeStyle::Plain => "━",
+            TreeStyle::Rounded => "─",
+            TreeStyle::Ascii => "-",
+        }
+    }
+
+    pub(crate) fn header_junction(self) -> &'static str {
+        match self {
+            TreeStyle::Plain => "╋",
+            TreeStyle::Rounded => "┼",
+            TreeStyle::Ascii => "+",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub(crate) struct Tree<Node> {
+    #[serde(flatten)]
     node: Node,
     children: Forest<Node>,
+    #[serde(skip)]
+    hidden_descendants: usize,
 }
 
-#[derive(Debug)]
+impl<Node> Tree<Node> {
+    fn is_collapsed(&self) -> bool {
+        self.hidden_descendants > 0
+    }
+
+    fn descendants(&self) -> usize {
+        self.hidden_descendants
+            + self
+                .children
+                .0
+                .iter()
+                .map(|child| 1 + child.descendants())
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Serialize)]
 pub(crate) struct Forest<Node>(Vec<Tree<Node>>);
 
 impl<Node> Forest<Node>
@@ -35,18 +138,22 @@ where
 
     pub(crate) fn new_forest(input: impl Iterator<Item = Node>) -> Self {
         let mut node_map = HashMap::new();
+        let mut ids = Vec::new();
+        for node in input {
+            // When ids are duplicated (e.g. through pid reuse), the last node wins.
+            let id = node.id();
+            if node_map.insert(id, node).is_none() {
+                ids.push(id);
+            }
+        }
         let mut children_map = HashMap::new();
         let mut roots = Vec::new();
-        for node in input {
-            if let Some(parent) = node.parent() {
-                children_map
-                    .entry(parent)
-                    .or_insert(Vec::new())
-                    .push(node.id());
+        for id in ids {
+            if let Some(parent) = node_map[&id].parent() {
+                children_map.entry(parent).or_insert(Vec::new()).push(id);
             } else {
-                roots.push(node.id());
+                roots.push(id);
             }
-            node_map.insert(node.id(), node);
         }
         let mut result = Forest::mk_forest(&mut node_map, &mut children_map, roots);
         result.compute_accumulate();
@@ -64,11 +171,16 @@ where
             result.0.push(Tree {
                 node: node_map.remove(&root).unwrap(),
                 children: Forest::mk_forest(node_map, children_map, children),
+                hidden_descendants: 0,
             });
         }
         result
     }
 
+    pub(crate) fn roots(&self) -> impl Iterator<Item = &Node> {
+        self.0.iter().map(|tree| &tree.node)
+    }
+
     pub(crate) fn iter(&self) -> impl Iterator<Item = &Node> {
         struct Iter<'a, Node>(VecDeque<&'a Tree<Node>>);
 
@@ -88,7 +200,7 @@ where
             }
         }
 
-        Iter(self.0.iter().rev().collect())
+        Iter(self.0.iter().collect())
     }
 
     pub(crate) fn sort_by<F>(&mut self, compare: &F)
@@ -101,6 +213,78 @@ where
         }
     }
 
+    pub(crate) fn for_each_mut<F>(&mut self, f: &F)
+    where
+        F: Fn(&mut Node),
+    {
+        for tree in &mut self.0 {
+            f(&mut tree.node);
+            tree.children.for_each_mut(f);
+        }
+    }
+
+    /// Returns the id of the given node followed by the ids of all its descendants, or nothing if
+    /// the node doesn't exist.
+    pub(crate) fn subtree_ids(&self, id: Node::Id) -> Vec<Node::Id> {
+        for tree in &self.0 {
+            if tree.node.id() == id {
+                let mut result = vec![id];
+                result.extend(tree.children.iter().map(Node::id));
+                return result;
+            }
+            let result = tree.children.subtree_ids(id);
+            if !result.is_empty() {
+                return result;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Replaces the forest with the subtree rooted at the given node. Returns false and leaves the
+    /// forest untouched if the node doesn't exist.
+    pub(crate) fn zoom(&mut self, id: Node::Id) -> bool {
+        match self.take_subtree(id) {
+            Some(tree) => {
+                self.0 = vec![tree];
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take_subtree(&mut self, id: Node::Id) -> Option<Tree<Node>> {
+        if let Some(index) = self.0.iter().position(|tree| tree.node.id() == id) {
+            return Some(self.0.swap_remove(index));
+        }
+        self.0
+            .iter_mut()
+            .find_map(|tree| tree.children.take_subtree(id))
+    }
+
+    /// Returns the ids of the parent, grandparent and so on of the given node, following
+    /// `Node::parent` for as long as the parents are part of the forest.
+    pub(crate) fn ancestor_ids(&self, id: Node::Id) -> Vec<Node::Id> {
+        let parents: HashMap<Node::Id, Option<Node::Id>> =
+            self.iter().map(|node| (node.id(), node.parent())).collect();
+        let mut result = Vec::new();
+        let mut current = id;
+        while let Some(Some(parent)) = parents.get(&current) {
+            if !parents.contains_key(parent) || result.contains(parent) {
+                break;
+            }
+            result.push(*parent);
+            current = *parent;
+        }
+        result
+    }
+
+    /// Sorts the roots by their number of descendants (largest first). The order of the children
+    /// is left untouched and ties keep their previous order.
+    pub(crate) fn sort_roots_by_descendants(&mut self) {
+        self.0
+            .sort_by_key(|tree| std::cmp::Reverse(tree.descendants()));
+    }
+
     fn compute_accumulate(&mut self) {
         for tree in &mut self.0 {
             tree.children.compute_accumulate();
@@ -137,17 +321,154 @@ where
         any_child_included
     }
 
-    pub(crate) fn render_forest_prefixes(&self) -> Vec<(String, &Node)> {
+    /// Removes all excluded nodes, except for the ones that still have non-excluded descendants.
+    /// Unlike `filter`, children of included nodes are checked as well.
+    pub(crate) fn exclude<F>(&mut self, exclude: &F) -> bool
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut old = Forest(Vec::new());
+        std::mem::swap(self, &mut old);
+        for mut tree in old.0 {
+            let any_child_included = tree.children.exclude(exclude);
+            if any_child_included || !exclude(&tree.node) {
+                self.0.push(tree);
+            }
+        }
+        !self.0.is_empty()
+    }
+
+    /// Removes the nodes for which `remove` returns true. Unlike `prune`, their children take
+    /// their place instead of being removed with them.
+    pub(crate) fn remove<F>(&mut self, remove: &F)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut old = Forest(Vec::new());
+        std::mem::swap(self, &mut old);
+        for mut tree in old.0 {
+            tree.children.remove(remove);
+            if remove(&tree.node) {
+                self.0.append(&mut tree.children.0);
+            } else {
+                self.0.push(tree);
+            }
+        }
+    }
+
+    pub(crate) fn prune<F>(&mut self, keep: &F)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        self.0.retain(|tree| keep(&tree.node));
+        for tree in &mut self.0 {
+            tree.children.prune(keep);
+        }
+    }
+
+    /// Replaces the forest with a flat list of the nodes that satisfy `keep`, in pre-order.
+    pub(crate) fn flatten<F>(&mut self, keep: &F)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        let mut old = Forest(Vec::new());
+        std::mem::swap(self, &mut old);
+        old.flatten_into(keep, &mut self.0);
+    }
+
+    fn flatten_into<F>(self, keep: &F, result: &mut Vec<Tree<Node>>)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        for tree in self.0 {
+            if keep(&tree.node) {
+                result.push(Tree {
+                    node: tree.node,
+                    children: Forest(Vec::new()),
+                    hidden_descendants: 0,
+                });
+            }
+            tree.children.flatten_into(keep, result);
+        }
+    }
+
+    /// Replaces the forest with a flat list of the nodes that don't have any children, in
+    /// pre-order.
+    pub(crate) fn keep_leaves(&mut self) {
+        let mut old = Forest(Vec::new());
+        std::mem::swap(self, &mut old);
+        old.leaves_into(&mut self.0);
+    }
+
+    fn leaves_into(self, result: &mut Vec<Tree<Node>>) {
+        for tree in self.0 {
+            if tree.children.0.is_empty() {
+                result.push(tree);
+            } else {
+                tree.children.leaves_into(result);
+            }
+        }
+    }
+
+    pub(crate) fn remove_children(&mut self) {
+        for tree in &mut self.0 {
+            tree.children = Forest(Vec::new());
+        }
+    }
+
+    pub(crate) fn collapse<F>(&mut self, is_collapsed: &F)
+    where
+        F: Fn(&Node) -> bool,
+    {
+        for tree in &mut self.0 {
+            if !tree.children.0.is_empty() && is_collapsed(&tree.node) {
+                tree.hidden_descendants = tree.descendants();
+                tree.children = Forest(Vec::new());
+            } else {
+                tree.children.collapse(is_collapsed);
+            }
+        }
+    }
+
+    /// Number of levels in the deepest tree, i.e. 1 for a forest without any children.
+    pub(crate) fn depth(&self) -> usize {
+        self.0
+            .iter()
+            .map(|tree| 1 + tree.children.depth())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Collapses all nodes on level `max_depth` (counting roots as level 1), so that no more than
+    /// `max_depth` levels are visible. The hidden nodes are still accumulated into their visible
+    /// ancestors.
+    pub(crate) fn limit_depth(&mut self, max_depth: usize) {
+        for tree in &mut self.0 {
+            if max_depth <= 1 {
+                if !tree.children.0.is_empty() {
+                    tree.hidden_descendants = tree.descendants();
+                    tree.children = Forest(Vec::new());
+                }
+            } else {
+                tree.children.limit_depth(max_depth - 1);
+            }
+        }
+    }
+
+    /// Returns one row per visible node: its tree-drawing prefix, the node itself and the number
+    /// of its descendants (including the ones hidden by collapsing).
+    pub(crate) fn render_forest_prefixes(&self, style: TreeStyle) -> Vec<(String, &Node, usize)> {
         let mut acc = Vec::new();
-        self.render_forest_prefixes_helper(true, &mut Vec::new(), &mut acc);
+        self.render_forest_prefixes_helper(style, true, &mut Vec::new(), &mut acc);
         acc
     }
 
     fn render_forest_prefixes_helper<'a>(
         &'a self,
+        style: TreeStyle,
         is_root: bool,
         prefixes: &mut Vec<&str>,
-        acc: &mut Vec<(String, &'a Node)>,
+        acc: &mut Vec<(String, &'a Node, usize)>,
     ) {
         for (i, child) in self.0.iter().enumerate() {
             let is_last = i == self.0.len() - 1;
@@ -156,17 +477,29 @@ where
                 line += prefix;
             }
             if !is_root {
-                line += if is_last { "└─" } else { "├─" };
+                line += if is_last {
+                    style.last_branch()
+                } else {
+                    style.branch()
+                };
                 let has_children = !child.children.0.is_empty();
-                line += if has_children { "┬ " } else { "─ " };
+                line += if child.is_collapsed() {
+                    style.collapsed()
+                } else if has_children {
+                    style.fork()
+                } else {
+                    style.leaf()
+                };
+            } else if child.is_collapsed() {
+                line += style.collapsed();
             }
-            acc.push((line, &child.node));
+            acc.push((line, &child.node, child.descendants()));
             if !(is_root) {
-                prefixes.push(if is_last { "  " } else { "│ " });
+                prefixes.push(if is_last { "  " } else { style.vertical() });
             }
             child
                 .children
-                .render_forest_prefixes_helper(false, prefixes, acc);
+                .render_forest_prefixes_helper(style, false, prefixes, acc);
             prefixes.pop();
         }
     }
@@ -186,7 +519,7 @@ mod test {
     {
         fn test_format(&self) -> String {
             let table: Vec<String> = self
-                .render_forest_prefixes()
+                .render_forest_prefixes(TreeStyle::default())
                 .into_iter()
                 .map(|x| format!("{}{}", x.0, x.1))
                 .collect();
@@ -348,6 +681,56 @@ mod test {
         );
     }
 
+    #[test]
+    fn g_allows_sorting_roots_by_descendants() {
+        let mut tree = Forest::new_forest(
+            vec![
+                TestNode::new(1, None),
+                TestNode::new(2, None),
+                TestNode::new(3, Some(2)),
+                TestNode::new(4, None),
+                TestNode::new(5, Some(4)),
+                TestNode::new(6, Some(5)),
+                TestNode::new(7, Some(4)),
+            ]
+            .into_iter(),
+        );
+        tree.sort_roots_by_descendants();
+        assert_eq!(
+            tree.test_format(),
+            "
+                four
+                ├─┬ five
+                │ └── six
+                └── seven
+                two
+                └── three
+                one
+            "
+            .unindent()
+        );
+    }
+
+    #[test]
+    fn j_duplicated_ids_keep_the_last_node() {
+        let tree = Forest::new_forest(
+            vec![
+                TestNode::new(1, None),
+                TestNode::new(2, Some(1)),
+                TestNode::new(2, None),
+            ]
+            .into_iter(),
+        );
+        assert_eq!(
+            tree.test_format(),
+            "
+                one
+                two
+            "
+            .unindent()
+        );
+    }
+
     mod h_filtering {
         use super::*;
         use pretty_assertions::assert_eq;
@@ -592,6 +975,400 @@ mod test {
         }
     }
 
+    mod l_collapsing {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_hides_children_of_collapsed_nodes() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, Some(4)),
+                ]
+                .into_iter(),
+            );
+            tree.collapse(&|node| node.id == 2);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    one
+                    ├─+ two
+                    └─┬ four
+                      └── five
+                "
+                .unindent()
+            );
+        }
+
+        #[test]
+        fn c_counts_hidden_descendants() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(2)),
+                    TestNode::new(5, Some(1)),
+                ]
+                .into_iter(),
+            );
+            tree.collapse(&|node| node.id == 2);
+            assert_eq!(
+                tree.render_forest_prefixes(TreeStyle::default())
+                    .into_iter()
+                    .map(|x| (x.1.id, x.2))
+                    .collect::<Vec<_>>(),
+                vec![(1, 4), (2, 2), (5, 0)]
+            );
+        }
+
+        #[test]
+        fn b_marks_collapsed_roots() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, None),
+                ]
+                .into_iter(),
+            );
+            tree.collapse(&|_| true);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    + one
+                    three
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod o_styles {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_renders_rounded_corners() {
+            let tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                ]
+                .into_iter(),
+            );
+            let table: Vec<String> = tree
+                .render_forest_prefixes(TreeStyle::Rounded)
+                .into_iter()
+                .map(|x| format!("{}{}", x.0, x.1))
+                .collect();
+            assert_eq!(
+                format!("{}\n", table.join("\n")),
+                "
+                    one
+                    ├─╮ two
+                    │ ╰── three
+                    ╰── four
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod p_ascii {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_renders_only_ascii_characters() {
+            let tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                ]
+                .into_iter(),
+            );
+            let table: Vec<String> = tree
+                .render_forest_prefixes(TreeStyle::Ascii)
+                .into_iter()
+                .map(|x| format!("{}{}", x.0, x.1))
+                .collect();
+            assert_eq!(
+                format!("{}\n", table.join("\n")),
+                "
+                    one
+                    |-+ two
+                    | `-- three
+                    `-- four
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod n_depth_limiting {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        fn four_levels() -> Forest<TestNode> {
+            Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(3)),
+                    TestNode::new(5, Some(1)),
+                    TestNode::new(6, None),
+                ]
+                .into_iter(),
+            )
+        }
+
+        #[test]
+        fn a_computes_the_depth() {
+            assert_eq!(four_levels().depth(), 4);
+            assert_eq!(Forest::<TestNode>::empty().depth(), 0);
+        }
+
+        #[test]
+        fn b_collapses_nodes_below_the_limit() {
+            let mut tree = four_levels();
+            tree.limit_depth(2);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    one
+                    ├─+ two
+                    └── five
+                    six
+                "
+                .unindent()
+            );
+            assert_eq!(tree.depth(), 2);
+        }
+
+        #[test]
+        fn c_counts_hidden_descendants() {
+            let mut tree = four_levels();
+            tree.limit_depth(1);
+            assert_eq!(
+                tree.render_forest_prefixes(TreeStyle::default())
+                    .into_iter()
+                    .map(|x| (x.1.id, x.2))
+                    .collect::<Vec<_>>(),
+                vec![(1, 4), (6, 0)]
+            );
+        }
+    }
+
+    mod p_excluding {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_removes_excluded_nodes_but_keeps_ancestors_of_included_ones() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            tree.exclude(&|node| node.id != 3 && node.id != 5);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    one
+                    └─┬ two
+                      └── three
+                    five
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod m_pruning {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_removes_whole_subtrees() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            tree.prune(&|node| node.id != 2 && node.id != 5);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    one
+                    └── four
+                "
+                .unindent()
+            );
+        }
+
+        #[test]
+        fn b_removing_nodes_moves_their_children_up() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            tree.remove(&|node| node.id == 1 || node.id == 5);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    two
+                    └── three
+                    four
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod q_subtrees {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_collects_a_node_and_all_its_descendants() {
+            let tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(3)),
+                    TestNode::new(5, Some(1)),
+                    TestNode::new(6, None),
+                ]
+                .into_iter(),
+            );
+            assert_eq!(tree.subtree_ids(2), vec![2, 3, 4]);
+            assert_eq!(tree.subtree_ids(1), vec![1, 2, 3, 4, 5]);
+            assert_eq!(tree.subtree_ids(7), Vec::<usize>::new());
+        }
+
+        #[test]
+        fn b_collects_the_ancestors_of_a_node() {
+            let tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                ]
+                .into_iter(),
+            );
+            assert_eq!(tree.ancestor_ids(3), vec![2, 1]);
+            assert_eq!(tree.ancestor_ids(4), vec![1]);
+            assert_eq!(tree.ancestor_ids(1), Vec::<usize>::new());
+            assert_eq!(tree.ancestor_ids(7), Vec::<usize>::new());
+        }
+
+        #[test]
+        fn c_zooms_into_a_subtree() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            assert!(!tree.zoom(7));
+            assert_eq!(tree.iter().count(), 5);
+            assert!(tree.zoom(2));
+            assert_eq!(
+                tree.test_format(),
+                "
+                    two
+                    └── three
+                "
+                .unindent()
+            );
+        }
+    }
+
+    mod r_flattening {
+        use super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn a_keeps_only_the_selected_nodes_without_their_ancestors() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            tree.flatten(&|node| node.id != 1 && node.id != 4);
+            assert_eq!(
+                tree.test_format(),
+                "
+                    two
+                    three
+                    five
+                "
+                .unindent()
+            );
+        }
+
+        #[test]
+        fn b_keeps_only_the_leaves() {
+            let mut tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, Some(2)),
+                    TestNode::new(4, Some(1)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            tree.keep_leaves();
+            assert_eq!(
+                tree.test_format(),
+                "
+                    three
+                    four
+                    five
+                "
+                .unindent()
+            );
+        }
+    }
+
     mod k_iterators {
         use super::*;
         use pretty_assertions::assert_eq;
@@ -613,5 +1390,23 @@ mod test {
                 vec![1, 2, 3, 4]
             );
         }
+
+        #[test]
+        fn b_iterates_through_multiple_roots_in_order() {
+            let tree = Forest::new_forest(
+                vec![
+                    TestNode::new(1, None),
+                    TestNode::new(2, Some(1)),
+                    TestNode::new(3, None),
+                    TestNode::new(4, Some(3)),
+                    TestNode::new(5, None),
+                ]
+                .into_iter(),
+            );
+            assert_eq!(
+                tree.iter().map(Node::id).collect::<Vec<usize>>(),
+                vec![1, 2, 3, 4, 5]
+            );
+        }
     }
 }
@@ -1,9 +1,20 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::process;
+use std::str::FromStr;
 
+use crate::cgroup::CpuQuotaSource;
+use crate::clipboard::Clipboard;
+use crate::help;
+use crate::highlight::style_spans;
+use crate::navigation_history::NavigationHistory;
 use crate::process::ProcessWatcher;
 use crate::process::SortBy;
 use crate::regex::Regex;
+use crate::signaler::Signaler;
+use crate::state::{State, StateFile};
 use crate::tree::Forest;
+use crate::usage_log::UsageLog;
+use crate::utils::{skip_width, truncate_line};
 use crate::Args;
 use crate::{
     process::Process,
@@ -11,574 +22,5144 @@ use crate::{
     tui_app::{self, UpdateResult},
     R,
 };
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use nix::errno::Errno;
-use nix::sys::signal::kill;
+use num_format::{Locale, ToFormattedString};
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::Stylize,
-    text::Line,
-    widgets::{List, ListState, Paragraph, StatefulWidget, Widget},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{List, ListState, Paragraph, StatefulWidget, Widget, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct TreetopApp {
     args: Args,
     process_watcher: ProcessWatcher,
+    signaler: Box<dyn Signaler>,
+    cpu_quota_source: CpuQuotaSource,
+    clipboard: Clipboard,
+    usage_log: Option<UsageLog>,
+    state_file: StateFile,
     forest: Forest<Process>,
     pattern: Regex,
+    /// Typed text that hasn't been compiled into `pattern` yet, see `debounced_update`
+    pending_pattern: Option<String>,
+    highlight: Option<Regex>,
+    hidden_self_matches: usize,
+    /// Number of processes before and after filtering by the pattern. Ancestors and descendants
+    /// that are shown together with matches are counted as matched.
+    total_processes: usize,
+    matched_processes: usize,
     list_state: ListState,
+    list_area: Rect,
+    /// Columns that the command of the given process is scrolled to the left
+    horizontal_scroll: Option<(sysinfo::Pid, usize)>,
     ui_mode: UiMode,
+    history: NavigationHistory<sysinfo::Pid>,
+    previous_names: HashMap<sysinfo::Pid, String>,
+    /// The selected process whose command changed, and for how many more refreshes that's shown
+    command_changed: Option<(sysinfo::Pid, u32)>,
+    termination: Option<(sysinfo::Pid, Termination)>,
     sort_column: SortBy,
+    descending: bool,
+    sort_roots_by_size: bool,
+    collapsed: HashSet<sysinfo::Pid>,
+    /// Collapsed subtrees restored from the state file, applied in the first refresh
+    restored_collapse_keys: BTreeSet<u64>,
+    /// The process given with '--select', selected in the first refresh
+    initial_selection: Option<sysinfo::Pid>,
+    /// Processes zoomed into with '>', innermost last. Only the subtree of the innermost one is
+    /// shown.
+    zoom: Vec<(sysinfo::Pid, String)>,
+    outline: bool,
+    flat: bool,
+    /// Show all processes as a flat list instead of a tree, like top
+    list_view: bool,
+    /// Only show processes without children, see 'l'.
+    only_leaves: bool,
+    /// Only highlight matches of the search pattern, without hiding any processes
+    highlight_only: bool,
+    full_path: bool,
+    threads: bool,
+    aggregate: bool,
+    max_depth: Option<usize>,
+    tree_depth: usize,
+    paused: bool,
+    min_cpu: Option<f32>,
+    min_ram_mb: Option<u64>,
+    threshold_input: String,
+    pid_input: String,
     error_state: Option<String>,
+    /// Whether the last refresh failed. The previous processes are kept until one succeeds.
+    refresh_failed: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum UiMode {
     Normal,
     EditingPattern,
+    EditingThreshold(Threshold),
+    EditingPid,
     ProcessSelected(sysinfo::Pid),
+    ConfirmingSignal {
+        pid: sysinfo::Pid,
+        signal: nix::sys::signal::Signal,
+        /// The number of descendants that get the signal as well, if it's for the whole subtree
+        descendants: Option<usize>,
+    },
+    /// Asking whether to send SIGTERM to all processes matching the search pattern
+    ConfirmingSignalToMatches,
+    /// Showing all keybindings over the whole screen
+    Help,
+}
+
+/// Progress of a selected process that got sent a signal that usually ends processes, see
+/// `ends_process`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Termination {
+    Waiting { ticks_left: u32 },
+    Terminated,
+    StillRunning,
+}
+
+impl Termination {
+    const TICKS_TO_WAIT: u32 = 3;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Threshold {
+    Cpu,
+    Ram,
 }
 
 impl TreetopApp {
-    pub(crate) fn new(process_watcher: ProcessWatcher, args: Args) -> R<TreetopApp> {
-        let pattern = args
-            .pattern
+    pub(crate) fn new(
+        process_watcher: ProcessWatcher,
+        signaler: Box<dyn Signaler>,
+        state_file: StateFile,
+        mut args: Args,
+    ) -> R<TreetopApp> {
+        // the header can't show that the tree is sorted by a hidden column
+        args.sort_columns.retain(|sort_by| {
+            *sort_by == SortBy::Name
+                || args
+                    .columns
+                    .iter()
+                    .any(|column| column.sort_by() == Some(*sort_by))
+        });
+        let state = state_file.load();
+        let mut pattern = match (&args.pattern, &state) {
+            (Some(pattern), _) => Regex::new(pattern)?,
+            (None, Some(state)) => Regex::new(&state.pattern).or_else(|_| Regex::empty())?,
+            (None, None) => Regex::empty()?,
+        };
+        if args.word {
+            pattern.toggle_whole_words();
+        }
+        if args.fuzzy {
+            pattern.toggle_fuzzy();
+        }
+        let highlight = args
+            .highlight
             .as_ref()
-            .map(|pattern| Regex::new(pattern))
-            .transpose()?
-            .unwrap_or(Regex::empty()?);
+            .map(|highlight| Regex::new(highlight))
+            .transpose()?;
+        let outline = args.outline;
+        let full_path = args.full_path;
+        let flat = args.flat;
+        let threads = args.threads;
+        let aggregate = !args.no_aggregate;
+        let max_depth = args.max_depth;
+        let sort_roots_by_size = args.sort_roots_by_size;
+        let restored_collapse_keys = state
+            .as_ref()
+            .map(|state| state.collapsed.clone())
+            .unwrap_or_default();
+        let initial_selection = args.select.map(sysinfo::Pid::from_u32);
+        let (sort_column, descending) = match (args.sort, state) {
+            (Some(sort_column), _) => (sort_column, sort_column.descending_by_default()),
+            (None, Some(state)) if args.sort_columns.contains(&state.sort_column) => {
+                (state.sort_column, state.descending)
+            }
+            _ => {
+                let sort_column = SortBy::initial(&args.sort_columns);
+                (sort_column, sort_column.descending_by_default())
+            }
+        };
         Ok(TreetopApp {
             args,
             process_watcher,
+            signaler,
+            cpu_quota_source: CpuQuotaSource::new(),
+            clipboard: Clipboard::new(),
+            usage_log: None,
+            state_file,
             forest: Forest::empty(),
             pattern,
+            pending_pattern: None,
+            highlight,
+            hidden_self_matches: 0,
+            total_processes: 0,
+            matched_processes: 0,
             list_state: ListState::default().with_selected(Some(0)),
+            list_area: Rect::default(),
+            horizontal_scroll: None,
             ui_mode: UiMode::Normal,
-            sort_column: SortBy::default(),
+            history: NavigationHistory::new(),
+            previous_names: HashMap::new(),
+            command_changed: None,
+            termination: None,
+            sort_column,
+            descending,
+            sort_roots_by_size,
+            collapsed: HashSet::new(),
+            restored_collapse_keys,
+            initial_selection,
+            zoom: Vec::new(),
+            outline,
+            flat,
+            list_view: false,
+            only_leaves: false,
+            highlight_only: false,
+            full_path,
+            threads,
+            aggregate,
+            max_depth,
+            tree_depth: 0,
+            paused: false,
+            min_cpu: None,
+            min_ram_mb: None,
+            threshold_input: String::new(),
+            pid_input: String::new(),
             error_state: None,
+            refresh_failed: false,
         })
     }
 
-    pub(crate) fn run(self) -> R<()> {
-        tui_app::run_ui(self)
+    pub(crate) fn run(mut self) -> R<()> {
+        // only opened here, since '--print' and the like don't refresh repeatedly
+        self.usage_log = self.args.log.as_deref().map(UsageLog::open).transpose()?;
+        let tick_length = self.args.interval();
+        let mouse_capture = !self.args.no_mouse;
+        let app = tui_app::run_ui(self, tick_length, mouse_capture)?;
+        app.state_file.save(&app.state())
     }
 
-    fn update_processes(&mut self) {
-        self.forest = self.process_watcher.get_forest();
-        self.forest
-            .sort_by(&|a, b| Process::compare(a, b, self.sort_column));
-        self.forest.filter(|p| {
-            p.is_match(
-                &self.pattern,
-                sysinfo::Pid::from_u32(process::id()),
-                &self.args,
-            )
-        });
-        if let UiMode::ProcessSelected(selected) = self.ui_mode {
-            if !self.forest.iter().any(|node| node.id() == selected) {
-                self.ui_mode = UiMode::Normal;
-            }
+    fn state(&self) -> State {
+        State {
+            pattern: self.pattern.as_str().to_string(),
+            sort_column: self.sort_column,
+            descending: self.descending,
+            collapsed: self
+                .process_watcher
+                .get_forest()
+                .iter()
+                .filter(|p| self.collapsed.contains(&p.id()))
+                .map(Process::collapse_key)
+                .collect(),
         }
     }
-}
 
-impl tui_app::TuiApp for TreetopApp {
-    fn update(&mut self, event: KeyEvent) -> R<UpdateResult> {
-        self.error_state = None;
-        #[allow(clippy::match_same_arms)]
-        match (event.modifiers, self.ui_mode, event.code) {
-            (KeyModifiers::CONTROL, _, KeyCode::Char('c'))
-            | (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('q')) => {
-                return Ok(UpdateResult::Exit);
-            }
-            (KeyModifiers::NONE, _, KeyCode::Up) => {
-                self.list_state.select(Some(
-                    self.list_state.selected().unwrap_or(0).saturating_sub(1),
-                ));
+    /// Returns whether any processes matched, see `found_matches`.
+    pub(crate) fn print(mut self) -> R<bool> {
+        self.process_watcher.refresh()?;
+        self.update_processes();
+        print!("{}", self.render_plain());
+        Ok(self.found_matches())
+    }
+
+    /// Returns whether any processes matched, see `found_matches`.
+    pub(crate) fn print_json(mut self) -> R<bool> {
+        self.process_watcher.refresh()?;
+        self.update_processes();
+        println!("{}", serde_json::to_string_pretty(&self.forest)?);
+        Ok(self.found_matches())
+    }
+
+    /// Returns whether any processes matched, see `found_matches`.
+    pub(crate) fn print_top(mut self, count: usize) -> R<bool> {
+        self.process_watcher.refresh()?;
+        self.aggregate = false;
+        self.update_processes();
+        let total_memory = self.process_watcher.total_memory();
+        let processes = self.top_processes(count);
+        let pid_width = Process::pid_width(processes.iter().copied());
+        for process in processes {
+            let row: String = process
+                .table_data(&self.args.columns, pid_width, total_memory, self.args.theme)
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect();
+            println!("{row} {process}");
+        }
+        Ok(self.found_matches())
+    }
+
+    /// The `count` processes with the highest cpu usage that match the pattern themselves, as
+    /// opposed to being shown as ancestors or descendants of matches.
+    fn top_processes(&self, count: usize) -> Vec<&Process> {
+        let treetop_pid = sysinfo::Pid::from_u32(process::id());
+        let is_match = |p: &Process| {
+            if self.pattern.as_str().is_empty() || self.pattern.is_entirely_invalid() {
+                true
+            } else {
+                p.is_match(&self.pattern, treetop_pid, &self.args) != self.pattern.is_negated()
             }
-            (KeyModifiers::NONE, _, KeyCode::PageUp) => {
-                self.list_state.select(Some(
-                    self.list_state.selected().unwrap_or(0).saturating_sub(20),
-                ));
+        };
+        let mut processes: Vec<&Process> = self
+            .forest
+            .iter()
+            // ancestors owned by other users are only kept for the tree
+            .filter(|p| {
+                !p.is_synthetic()
+                    && is_match(p)
+                    && self
+                        .args
+                        .user
+                        .as_ref()
+                        .map_or(true, |user| p.is_owned_by(user))
+            })
+            .collect();
+        processes.sort_by(|a, b| Process::compare(a, b, SortBy::Cpu, true, self.args.stable));
+        processes.truncate(count);
+        processes
+    }
+
+    /// Whether the search pattern left any processes visible. Always true without a pattern.
+    fn found_matches(&self) -> bool {
+        self.pattern.as_str().is_empty() || self.forest.iter().next().is_some()
+    }
+
+    fn render_plain(&self) -> String {
+        let total_memory = self.process_watcher.total_memory();
+        let pid_width = Process::pid_width(self.forest.iter());
+        let mut result = String::new();
+        for (prefix, process, _) in self.forest.render_forest_prefixes(self.args.tree_style) {
+            for span in
+                process.table_data(&self.args.columns, pid_width, total_memory, self.args.theme)
+            {
+                result.push_str(&span.content);
             }
-            (KeyModifiers::NONE, _, KeyCode::Down) => {
-                self.list_state.select(Some(
-                    self.list_state.selected().unwrap_or(0).saturating_add(1),
-                ));
+            result.push_str(&format!(" {prefix}{process}\n"));
+        }
+        result
+    }
+
+    /// Fetches the current processes. Called on every tick, and on demand with F5. If that fails,
+    /// the previous processes are kept.
+    fn refresh(&mut self) {
+        let selected = self.selected_pid();
+        if !self.refresh_watcher() {
+            return;
+        }
+        let forest = self
+            .process_watcher
+            .get_forest_with(self.threads, self.args.group_kernel);
+        let restored_collapse_keys = std::mem::take(&mut self.restored_collapse_keys);
+        if !restored_collapse_keys.is_empty() {
+            self.collapsed.extend(
+                forest
+                    .iter()
+                    .filter(|p| restored_collapse_keys.contains(&p.collapse_key()))
+                    .map(Node::id),
+            );
+        }
+        self.append_usage_log(&forest);
+        self.update_processes_from(forest);
+        if let Some(pid) = self.initial_selection.take() {
+            if self.jump_to(pid) {
+                self.history.push(pid);
             }
-            (KeyModifiers::NONE, _, KeyCode::PageDown) => {
-                self.list_state.select(Some(
-                    self.list_state.selected().unwrap_or(0).saturating_add(20),
-                ));
+        } else if let Some(pid) = selected {
+            self.follow_selection(pid);
+        }
+        self.track_name_changes();
+    }
+
+    /// Returns whether refreshing succeeded. Failures are shown as errors.
+    fn refresh_watcher(&mut self) -> bool {
+        let result = self.process_watcher.refresh();
+        self.refresh_failed = result.is_err();
+        if let Err(error) = result {
+            self.error_state = Some(error.to_string());
+        }
+        !self.refresh_failed
+    }
+
+    fn update_processes(&mut self) {
+        if self.refresh_failed {
+            return;
+        }
+        let forest = self
+            .process_watcher
+            .get_forest_with(self.threads, self.args.group_kernel);
+        self.update_processes_from(forest);
+    }
+
+    /// Filters, sorts and otherwise prepares the given forest of all processes for showing it.
+    fn update_processes_from(&mut self, forest: Forest<Process>) {
+        self.forest = forest;
+        if self.pattern.uses_parent_names() {
+            let names: HashMap<sysinfo::Pid, String> = self
+                .forest
+                .iter()
+                .map(|p| (p.id(), p.name.clone()))
+                .collect();
+            self.forest.for_each_mut(&|p| p.resolve_parent_name(&names));
+        }
+        if self.args.container_cpu {
+            if let Some(cores) = self.cpu_quota_source.cores() {
+                self.forest
+                    .for_each_mut(&|p| p.make_cpu_relative_to_quota(cores));
             }
-            (KeyModifiers::NONE, UiMode::EditingPattern, KeyCode::Enter) => {
-                self.ui_mode = UiMode::Normal;
+        }
+        while let Some((root, _)) = self.zoom.last() {
+            if self.forest.zoom(*root) {
+                break;
             }
-            (KeyModifiers::NONE, _, KeyCode::Enter) => {
-                if let Some(selected) = self.list_state.selected() {
-                    if let Some(process) = self
-                        .forest
-                        .render_forest_prefixes()
-                        .into_iter()
-                        .nth(selected)
-                    {
-                        self.ui_mode = UiMode::ProcessSelected(process.1.id());
-                    }
-                }
+            self.zoom.pop();
+        }
+        if !self.aggregate || self.list_view {
+            self.forest.for_each_mut(&Process::show_own_usage);
+        }
+        if self.full_path {
+            self.forest.for_each_mut(&Process::show_full_path);
+        }
+        self.forest.sort_by(&|a, b| {
+            Process::compare(a, b, self.sort_column, self.descending, self.args.stable)
+        });
+        if self.sort_roots_by_size {
+            self.forest.sort_roots_by_descendants();
+        }
+        let count_processes =
+            |forest: &Forest<Process>| forest.iter().filter(|p| !p.is_synthetic()).count();
+        self.total_processes = count_processes(&self.forest);
+        let treetop_pid = sysinfo::Pid::from_u32(process::id());
+        if self.highlight_only {
+            self.hidden_self_matches = 0;
+        } else if self.pattern.is_negated() {
+            self.hidden_self_matches = 0;
+            self.forest
+                .exclude(&|p| p.is_match(&self.pattern, treetop_pid, &self.args));
+        } else if self.pattern.is_entirely_invalid() {
+            // keep showing everything until the pattern is fixed, invalid terms next to valid ones
+            // just don't match anything
+            self.hidden_self_matches = 0;
+        } else {
+            self.hidden_self_matches = self
+                .forest
+                .iter()
+                .filter(|p| p.is_hidden_self_match(&self.pattern, treetop_pid, &self.args))
+                .count();
+            self.forest
+                .filter(|p| p.is_match(&self.pattern, treetop_pid, &self.args));
+            if (self.flat || self.list_view) && !self.pattern.as_str().is_empty() {
+                self.forest
+                    .flatten(&|p| p.is_match(&self.pattern, treetop_pid, &self.args));
+                self.forest.sort_by(&|a, b| {
+                    Process::compare(a, b, self.sort_column, self.descending, self.args.stable)
+                });
             }
-            (KeyModifiers::NONE, _, KeyCode::Char('/')) => {
-                self.ui_mode = UiMode::EditingPattern;
+        }
+        let is_hidden = |p: &Process| self.args.hide.contains(&p.name);
+        if !self.args.hide.is_empty() {
+            self.forest.exclude(&is_hidden);
+        }
+        let is_other_user = |p: &Process| {
+            self.args
+                .user
+                .as_ref()
+                .is_some_and(|user| !p.is_owned_by(user))
+        };
+        if self.args.user_only {
+            self.forest.remove(&is_other_user);
+        } else if self.args.user.is_some() {
+            self.forest.exclude(&is_other_user);
+        }
+        if self.list_view {
+            // `exclude` keeps excluded ancestors for their descendants, which the list doesn't need
+            let excluded = |p: &Process| {
+                is_hidden(p)
+                    || is_other_user(p)
+                    || (self.pattern.is_negated()
+                        && !self.highlight_only
+                        && p.is_match(&self.pattern, treetop_pid, &self.args))
+            };
+            self.forest.flatten(&|p| !excluded(p));
+            self.forest.sort_by(&|a, b| {
+                Process::compare(a, b, self.sort_column, self.descending, self.args.stable)
+            });
+        }
+        if self.only_leaves {
+            self.forest.keep_leaves();
+        }
+        self.matched_processes = count_processes(&self.forest);
+        if let Some(min_cpu) = self.min_cpu {
+            self.forest.prune(&|p| p.cpu() >= min_cpu);
+        }
+        if let Some(min_ram_mb) = self.min_ram_mb {
+            self.forest
+                .prune(&|p| p.ram() >= min_ram_mb.saturating_mul(2_u64.pow(20)));
+        }
+        if self.args.root_only {
+            self.forest.remove_children();
+        }
+        self.forest.collapse(&|p| self.collapsed.contains(&p.id()));
+        self.tree_depth = self.forest.depth();
+        if let Some(max_depth) = self.max_depth {
+            self.forest.limit_depth(max_depth);
+        }
+        if let UiMode::ProcessSelected(selected) | UiMode::ConfirmingSignal { pid: selected, .. } =
+            self.ui_mode
+        {
+            if !self.forest.iter().any(|node| node.id() == selected) {
+                self.ui_mode = UiMode::Normal;
             }
-            (KeyModifiers::NONE, _, KeyCode::Tab) => {
-                self.sort_column = self.sort_column.next();
+        }
+    }
+
+    fn is_synthetic(&self, pid: sysinfo::Pid) -> bool {
+        self.forest
+            .iter()
+            .any(|p| p.id() == pid && p.is_synthetic())
+    }
+
+    const HORIZONTAL_SCROLL_STEP: usize = 8;
+
+    fn scroll_horizontally(&mut self, right: bool) {
+        let Some(pid) = self.selected_pid() else {
+            return;
+        };
+        let Some(command_width) = self
+            .forest
+            .iter()
+            .find(|p| p.id() == pid)
+            .map(|p| p.to_string().width())
+        else {
+            return;
+        };
+        let offset = self.horizontal_offset(pid);
+        let offset = if right {
+            (offset + TreetopApp::HORIZONTAL_SCROLL_STEP).min(command_width.saturating_sub(1))
+        } else {
+            offset.saturating_sub(TreetopApp::HORIZONTAL_SCROLL_STEP)
+        };
+        self.horizontal_scroll = (offset > 0).then_some((pid, offset));
+    }
+
+    fn horizontal_offset(&self, pid: sysinfo::Pid) -> usize {
+        match self.horizontal_scroll {
+            Some((scrolled, offset)) if scrolled == pid => offset,
+            _ => 0,
+        }
+    }
+
+    fn reset_horizontal_scroll_on_selection_change(&mut self) {
+        if let Some((pid, _)) = self.horizontal_scroll {
+            if self.selected_pid() != Some(pid) {
+                self.horizontal_scroll = None;
             }
+        }
+    }
 
-            // mode specific actions
-            (
-                KeyModifiers::NONE,
-                UiMode::EditingPattern | UiMode::ProcessSelected(_),
-                KeyCode::Esc,
-            ) => {
-                self.ui_mode = UiMode::Normal;
+    fn selected_pid(&self) -> Option<sysinfo::Pid> {
+        let selected = self.list_state.selected()?;
+        self.forest
+            .render_forest_prefixes(self.args.tree_style)
+            .into_iter()
+            .nth(selected)
+            .map(|row| row.1.id())
+    }
+
+    /// Moves the cursor to the row of the given process. Sets an error if it's not shown.
+    fn scroll_to(&mut self, pid: sysinfo::Pid) -> bool {
+        match self.row_of(pid) {
+            Some(index) => {
+                self.list_state.select(Some(index));
+                true
             }
-            (KeyModifiers::NONE, UiMode::EditingPattern, KeyCode::Char(key)) if key.is_ascii() => {
-                self.pattern.modify(|pattern| pattern.push(key));
+            None => {
+                self.error_state = Some(format!("process {pid} is not shown"));
+                false
             }
-            (KeyModifiers::NONE, UiMode::EditingPattern, KeyCode::Backspace) => {
-                self.pattern.modify(|pattern| {
-                    pattern.pop();
-                });
+        }
+    }
+
+    /// Keeps the cursor on the given process when rows move around. If the process is gone, the
+    /// cursor stays at the same index.
+    fn follow_selection(&mut self, pid: sysinfo::Pid) {
+        if let Some(index) = self.row_of(pid) {
+            self.list_state.select(Some(index));
+        }
+    }
+
+    /// The index of the process shown in the given terminal row, as of the last rendering.
+    fn row_at(&self, y: u16) -> Option<usize> {
+        if y < self.list_area.y || y >= self.list_area.y + self.list_area.height {
+            return None;
+        }
+        let index = self.list_state.offset() + usize::from(y - self.list_area.y);
+        let rows = self
+            .forest
+            .render_forest_prefixes(self.args.tree_style)
+            .len();
+        (index < rows).then_some(index)
+    }
+
+    fn row_of(&self, pid: sysinfo::Pid) -> Option<usize> {
+        self.forest
+            .render_forest_prefixes(self.args.tree_style)
+            .into_iter()
+            .position(|row| row.1.id() == pid)
+    }
+
+    /// Moves the cursor to the given process and selects it. Synthetic processes are only
+    /// scrolled to, since signals can't be sent to them. Returns whether it was selected.
+    fn jump_to(&mut self, pid: sysinfo::Pid) -> bool {
+        if self.scroll_to(pid) && !self.is_synthetic(pid) {
+            self.ui_mode = UiMode::ProcessSelected(pid);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn apply_pid_input(&mut self) {
+        let input = self.pid_input.trim();
+        match input.parse::<u32>() {
+            Ok(pid) => {
+                self.scroll_to(sysinfo::Pid::from_u32(pid));
             }
-            (
-                KeyModifiers::NONE,
-                UiMode::ProcessSelected(pid),
-                KeyCode::Char(char @ ('t' | 'k')),
-            ) => {
-                match kill(
-                    nix::unistd::Pid::from_raw(pid.as_u32().try_into()?),
-                    match char {
-                        't' => nix::sys::signal::Signal::SIGTERM,
-                        'k' => nix::sys::signal::Signal::SIGKILL,
-                        _ => unreachable!("should be 't' or 'k'"),
-                    },
-                ) {
-                    Ok(()) => {}
-                    Err(Errno::EPERM) => {
-                        self.error_state = Some("missing permissions to send signal".to_string());
-                    }
-                    Err(e) => Err(e)?,
-                }
+            Err(_) => {
+                self.error_state = Some(format!("invalid pid: {input}"));
             }
-            _ => {}
         }
-        self.update_processes();
-        Ok(UpdateResult::Continue)
     }
 
-    fn render(&mut self, area: Rect, buffer: &mut Buffer) {
-        let header_height = Process::render_header(area, self.sort_column, buffer);
-        let list_rect = Rect {
-            x: area.x,
-            y: area.y + header_height,
-            width: area.width,
-            height: area.height
-                - header_height
-                - 1
-                - if self.error_state.is_some() { 1 } else { 0 },
+    fn apply_threshold_input(&mut self, threshold: Threshold) {
+        let input = self.threshold_input.trim();
+        let valid = match threshold {
+            Threshold::Cpu => parse_threshold(input)
+                .map(|min_cpu| self.min_cpu = min_cpu)
+                .is_ok(),
+            Threshold::Ram => parse_threshold(input)
+                .map(|min_ram_mb| self.min_ram_mb = min_ram_mb)
+                .is_ok(),
         };
-        let list = self.forest.render_forest_prefixes();
-        normalize_list_state(&mut self.list_state, &list, list_rect);
-        let tree_lines = list.iter().enumerate().map(|(i, x)| {
-            let mut line = Line::default();
-            line.push_span(format!("{} ", x.1.table_data()));
-            line.push_span("┃".dark_gray());
-            line.push_span(if self.list_state.selected() == Some(i) {
-                " ▶ "
-            } else {
-                "   "
-            });
-            line.push_span(x.0.as_str().blue());
-            line.push_span(if self.ui_mode == UiMode::ProcessSelected(x.1.id()) {
-                x.1.to_string().reversed().blue()
-            } else {
-                x.1.to_string().not_reversed()
-            });
-            line
-        });
-        StatefulWidget::render(
-            List::new(tree_lines),
-            list_rect,
-            buffer,
-            &mut self.list_state,
-        );
-        if let Some(error) = &self.error_state {
-            Paragraph::new(format!("Error: {error}"))
-                .red()
-                .bold()
-                .reversed()
-                .render(
-                    Rect {
-                        x: area.x,
-                        y: area.height - 2,
-                        width: area.width,
-                        height: 1,
-                    },
-                    buffer,
-                );
+        if !valid {
+            self.error_state = Some(format!("invalid threshold: {input}"));
+        }
+    }
+
+    fn status_notes(&self) -> Vec<String> {
+        let mut result = Vec::new();
+        if self.paused {
+            result.push("PAUSED".to_string());
+        }
+        if !self.zoom.is_empty() {
+            let breadcrumbs: Vec<String> = self
+                .zoom
+                .iter()
+                .map(|(pid, name)| format!("{name} ({pid})"))
+                .collect();
+            result.push(format!("zoom: {}", breadcrumbs.join(" › ")));
         }
+        if let (UiMode::ProcessSelected(pid), Some((changed, _))) =
+            (self.ui_mode, self.command_changed)
         {
-            let status_bar = match self.ui_mode {
-                UiMode::Normal => {
-                    let mut commands = vec![
-                        "Ctrl+C: Quit".to_string(),
-                        "↑↓ : scroll".to_string(),
-                        "ENTER: select process".to_string(),
-                        "/: filter processes".to_string(),
-                    ];
-                    if !self.pattern.as_str().is_empty() {
-                        commands.push(format!("search pattern: {}", self.pattern.as_str()));
-                    }
-                    commands.join(" | ")
-                }
-                UiMode::EditingPattern => [
-                    "Ctrl+C: Quit",
-                    "↑↓ : scroll",
-                    "ENTER: select process",
-                    "ESC: exit search mode",
-                    &format!("type search pattern: {}▌", self.pattern.as_str()),
-                ]
-                .join(" | "),
-                UiMode::ProcessSelected(_pid) => {
-                    let mut commands = vec![
-                        "Ctrl+C: Quit".to_string(),
-                        "↑↓ : scroll".to_string(),
-                        "t: SIGTERM process".to_string(),
-                        "k: SIGKILL process".to_string(),
-                        "ESC: unselect".to_string(),
-                        "ENTER: select other".to_string(),
-                    ];
-                    if !self.pattern.as_str().is_empty() {
-                        commands.push(format!("search pattern: {}", self.pattern.as_str()));
-                    }
-                    commands.join(" | ")
-                }
-            };
-            let mut status_bar = Paragraph::new(status_bar).reversed();
-            match self.ui_mode {
-                UiMode::Normal => {}
-                UiMode::EditingPattern => {
-                    status_bar = status_bar.yellow();
-                }
-                UiMode::ProcessSelected(_) => {
-                    status_bar = status_bar.blue();
-                }
+            if pid == changed {
+                result.push("command changed".to_string());
             }
-            status_bar.render(
-                Rect {
-                    x: area.x,
-                    y: area.height - 1,
-                    width: area.width,
-                    height: 1,
-                },
-                buffer,
-            );
         }
+        match self.termination {
+            Some((pid, Termination::Waiting { .. })) => {
+                result.push(format!("waiting for {pid} to exit"));
+            }
+            Some((pid, Termination::Terminated)) => {
+                result.push(format!("{pid} terminated"));
+            }
+            Some((pid, Termination::StillRunning)) => {
+                result.push(format!("{pid} still running — press K to SIGKILL"));
+            }
+            None => {}
+        }
+        if !self.pattern.as_str().is_empty() {
+            result.push(format!(
+                "{}: {}",
+                self.pattern_label(),
+                self.pattern.as_str()
+            ));
+        }
+        if let Some(max_depth) = self.max_depth {
+            result.push(format!("max depth: {max_depth}"));
+        }
+        if let Some(min_cpu) = self.min_cpu {
+            result.push(format!("min cpu: {min_cpu}%"));
+        }
+        if let Some(min_ram_mb) = self.min_ram_mb {
+            result.push(format!("min ram: {min_ram_mb}MB"));
+        }
+        if self.hidden_self_matches > 0 {
+            result.push(format!(
+                "{} self-match hidden (pass --dont-hide-self to show)",
+                self.hidden_self_matches
+            ));
+        }
+        result
     }
 
-    fn tick(&mut self) {
-        self.process_watcher.refresh();
-        self.update_processes();
+    /// The pattern as typed so far, including keystrokes that haven't been applied yet.
+    fn pattern_text(&self) -> &str {
+        self.pending_pattern
+            .as_deref()
+            .unwrap_or_else(|| self.pattern.as_str())
     }
-}
 
-fn normalize_list_state<T>(list_state: &mut ListState, list: &[T], rect: Rect) {
-    if let Some(ref mut selected) = list_state.selected_mut() {
-        *selected = (*selected).min(list.len().saturating_sub(1));
+    /// Whether the pattern as typed so far is invalid, see `pattern_text`.
+    fn pattern_text_is_invalid(&self) -> bool {
+        match &self.pending_pattern {
+            Some(pending) => self.pattern.with_pattern(pending.clone()).is_invalid(),
+            None => self.pattern.is_invalid(),
+        }
     }
-    *list_state.offset_mut() = list_state
-        .offset()
-        .min(list.len().saturating_sub(rect.height.into()));
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::tui_app::TuiApp;
-    use crossterm::event::{KeyEventKind, KeyEventState};
-    use insta::assert_snapshot;
-    use ratatui::buffer::Cell;
-    use ratatui::layout::Rect;
-    use ratatui::style::Modifier;
-    use ratatui::widgets::ListState;
+    fn apply_pending_pattern(&mut self) {
+        if let Some(pending) = self.pending_pattern.take() {
+            self.pattern.modify(|pattern| *pattern = pending);
+        }
+    }
+
+    fn pattern_label(&self) -> String {
+        let label = if self.pattern.is_fuzzy() {
+            "search fuzzy"
+        } else if self.pattern.is_literal() {
+            "search literal"
+        } else {
+            "search pattern"
+        };
+        let mut label = label.to_string();
+        if self.pattern.is_whole_words() {
+            label.push_str(" (whole words)");
+        }
+        if self.highlight_only {
+            label.push_str(" (highlight only)");
+        }
+        label
+    }
+
+    /// How many refreshes the 'command changed' note is shown for.
+    const COMMAND_CHANGED_TICKS: u32 = 5;
+
+    fn track_name_changes(&mut self) {
+        let names: HashMap<sysinfo::Pid, String> = self
+            .forest
+            .iter()
+            .map(|p| (p.id(), p.name.clone()))
+            .collect();
+        let selected = match self.ui_mode {
+            UiMode::ProcessSelected(pid) => Some(pid),
+            _ => None,
+        };
+        self.command_changed = match self.command_changed {
+            Some((pid, ticks_left)) if Some(pid) == selected && ticks_left > 1 => {
+                Some((pid, ticks_left - 1))
+            }
+            _ => None,
+        };
+        if let Some(pid) = selected {
+            if let (Some(previous), Some(current)) =
+                (self.previous_names.get(&pid), names.get(&pid))
+            {
+                if previous != current {
+                    self.command_changed = Some((pid, TreetopApp::COMMAND_CHANGED_TICKS));
+                }
+            }
+        }
+        self.previous_names = names;
+    }
+
+    fn track_termination(&mut self) {
+        let Some((pid, state)) = self.termination else {
+            return;
+        };
+        let running = self
+            .process_watcher
+            .get_forest()
+            .iter()
+            .any(|p| p.id() == pid);
+        self.termination = match state {
+            Termination::Waiting { .. } | Termination::StillRunning if !running => {
+                Some((pid, Termination::Terminated))
+            }
+            Termination::Waiting { ticks_left } if ticks_left <= 1 => {
+                Some((pid, Termination::StillRunning))
+            }
+            Termination::Waiting { ticks_left } => Some((
+                pid,
+                Termination::Waiting {
+                    ticks_left: ticks_left - 1,
+                },
+            )),
+            state => Some((pid, state)),
+        };
+        self.forget_unselected_termination();
+    }
+
+    /// Forgets that a process survived a signal once it's not selected anymore, so that the
+    /// status bar doesn't offer to kill a process that's out of sight.
+    fn forget_unselected_termination(&mut self) {
+        if let Some((pid, Termination::StillRunning)) = self.termination {
+            let selected = match self.ui_mode {
+                UiMode::ProcessSelected(selected)
+                | UiMode::ConfirmingSignal { pid: selected, .. } => Some(selected),
+                _ => None,
+            };
+            if selected != Some(pid) {
+                self.termination = None;
+            }
+        }
+    }
+
+    fn send_signal_and_wait(
+        &mut self,
+        pid: sysinfo::Pid,
+        signal: nix::sys::signal::Signal,
+    ) -> R<()> {
+        let result = self
+            .signaler
+            .send(nix::unistd::Pid::from_raw(pid.as_u32().try_into()?), signal);
+        if result.is_ok() {
+            self.termination = Some((
+                pid,
+                Termination::Waiting {
+                    ticks_left: Termination::TICKS_TO_WAIT,
+                },
+            ));
+        }
+        self.handle_signal_result(result)
+    }
+
+    /// The command of the selected process, as shown in the tree.
+    fn selected_command(&self) -> Option<String> {
+        let pid = match self.ui_mode {
+            UiMode::ProcessSelected(pid) => pid,
+            _ => self.selected_pid()?,
+        };
+        self.forest
+            .iter()
+            .find(|process| process.id() == pid)
+            .map(Process::command)
+    }
+
+    fn copy_selected_command(&mut self) {
+        if let Some(command) = self.selected_command() {
+            if let Err(error) = self.clipboard.copy(&command) {
+                self.error_state = Some(format!("cannot copy to clipboard: {error}"));
+            }
+        }
+    }
+
+    /// Logs the total usage of all processes in `forest`, which mustn't be filtered yet.
+    fn append_usage_log(&mut self, forest: &Forest<Process>) {
+        if let Some(usage_log) = &mut self.usage_log {
+            let cpu = forest.roots().map(Process::cpu).sum();
+            let ram = forest.roots().map(Process::ram).sum();
+            if let Err(error) = usage_log.append(cpu, ram) {
+                self.error_state = Some(format!("cannot write log: {error}"));
+            }
+        }
+    }
+
+    fn send_signal(&mut self, pid: sysinfo::Pid, signal: nix::sys::signal::Signal) -> R<()> {
+        if ends_process(signal) {
+            return self.send_signal_and_wait(pid, signal);
+        }
+        let result = self
+            .signaler
+            .send(nix::unistd::Pid::from_raw(pid.as_u32().try_into()?), signal);
+        self.handle_signal_result(result)
+    }
+
+    /// Sends SIGTERM or SIGKILL to the given process and all its descendants, including the
+    /// ones that are currently hidden. Failures are collected and reported together.
+    fn send_signal_to_subtree(
+        &mut self,
+        pid: sysinfo::Pid,
+        signal: nix::sys::signal::Signal,
+    ) -> R<()> {
+        let pids = self.subtree_ids(pid);
+        self.send_signal_to_all(&pids, signal)
+    }
+
+    /// The given process and all its descendants in the forest that is currently shown, i.e.
+    /// including threads with `--threads`, but regardless of collapsing and filtering.
+    fn subtree_ids(&self, pid: sysinfo::Pid) -> Vec<sysinfo::Pid> {
+        self.process_watcher
+            .get_forest_with(self.threads, self.args.group_kernel)
+            .subtree_ids(pid)
+    }
+
+    /// Processes that match the search pattern themselves (as opposed to ancestors that are only
+    /// shown for context). Empty for empty and negated patterns, and never includes treetop.
+    fn matching_pids(&self) -> Vec<sysinfo::Pid> {
+        if self.pattern.as_str().is_empty() || self.pattern.is_negated() {
+            return Vec::new();
+        }
+        let treetop_pid = sysinfo::Pid::from_u32(process::id());
+        self.forest
+            .iter()
+            .filter(|p| {
+                p.id() != treetop_pid
+                    && !p.is_synthetic()
+                    && p.is_match(&self.pattern, treetop_pid, &self.args)
+            })
+            .map(Node::id)
+            .collect()
+    }
+
+    fn request_signal_to_matches(&mut self) -> R<()> {
+        if self.matching_pids().is_empty() {
+            self.error_state = Some("no processes match the search pattern".to_string());
+        } else if self.args.no_confirm {
+            self.send_signal_to_all(&self.matching_pids(), nix::sys::signal::Signal::SIGTERM)?;
+        } else {
+            self.ui_mode = UiMode::ConfirmingSignalToMatches;
+        }
+        Ok(())
+    }
+
+    /// Signals all given processes, summarizing failures in the error bar. Processes that
+    /// disappeared in the meantime are ignored.
+    fn send_signal_to_all(
+        &mut self,
+        pids: &[sysinfo::Pid],
+        signal: nix::sys::signal::Signal,
+    ) -> R<()> {
+        let mut failures = Vec::new();
+        for pid in pids {
+            match self
+                .signaler
+                .send(nix::unistd::Pid::from_raw(pid.as_u32().try_into()?), signal)
+            {
+                Ok(()) | Err(Errno::ESRCH) => {}
+                Err(errno) => failures.push(format!("{pid} ({errno:?})")),
+            }
+        }
+        if !failures.is_empty() {
+            self.error_state = Some(format!(
+                "failed to signal {} of {} processes: {}",
+                failures.len(),
+                pids.len(),
+                failures.join(", ")
+            ));
+        }
+        self.refresh_watcher();
+        Ok(())
+    }
+
+    fn request_signal(
+        &mut self,
+        pid: sysinfo::Pid,
+        signal: nix::sys::signal::Signal,
+        subtree: bool,
+    ) -> R<()> {
+        if !self.args.no_confirm {
+            let descendants = subtree.then(|| self.subtree_ids(pid).len().saturating_sub(1));
+            self.ui_mode = UiMode::ConfirmingSignal {
+                pid,
+                signal,
+                descendants,
+            };
+        } else if subtree {
+            self.send_signal_to_subtree(pid, signal)?;
+        } else {
+            self.send_signal(pid, signal)?;
+        }
+        Ok(())
+    }
+
+    fn confirm_signal(
+        &mut self,
+        event: KeyEvent,
+        pid: sysinfo::Pid,
+        signal: nix::sys::signal::Signal,
+        descendants: Option<usize>,
+    ) -> R<()> {
+        self.ui_mode = UiMode::ProcessSelected(pid);
+        if let (KeyModifiers::NONE, KeyCode::Char('y') | KeyCode::Enter) =
+            (event.modifiers, event.code)
+        {
+            if descendants.is_some() {
+                self.send_signal_to_subtree(pid, signal)?;
+            } else {
+                self.send_signal(pid, signal)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn confirm_signal_to_matches(&mut self, event: KeyEvent) -> R<()> {
+        self.ui_mode = UiMode::Normal;
+        if let (KeyModifiers::NONE, KeyCode::Char('y') | KeyCode::Enter) =
+            (event.modifiers, event.code)
+        {
+            self.send_signal_to_all(&self.matching_pids(), nix::sys::signal::Signal::SIGTERM)?;
+        }
+        Ok(())
+    }
+
+    fn render_summary(&self, area: Rect, buffer: &mut Buffer) -> u16 {
+        let mb = |bytes: u64| (bytes / 2_u64.pow(20)).to_formatted_string(&Locale::en);
+        let processes = if self.pattern.as_str().is_empty() {
+            self.total_processes.to_string()
+        } else {
+            format!("{}/{}", self.matched_processes, self.total_processes)
+        };
+        let summary = format!(
+            "cpu: {:.1}% | ram: {}MB / {}MB | processes: {}",
+            self.process_watcher.global_cpu(),
+            mb(self.process_watcher.used_memory()),
+            mb(self.process_watcher.total_memory()),
+            processes,
+        );
+        buffer.set_line(area.x, area.y, &Line::from(summary), area.width);
+        1
+    }
+
+    /// The command hints shown in the status bar, and the text being typed in, if any.
+    fn status_bar_parts(&self) -> (Vec<String>, Option<String>) {
+        let hints = |hints: &[&str]| hints.iter().map(ToString::to_string).collect();
+        match self.ui_mode {
+            UiMode::Normal => {
+                let mut commands: Vec<String> = hints(&[
+                    "Ctrl+C: Quit",
+                    "↑↓ : scroll",
+                    "ENTER: select process",
+                    "/: filter processes",
+                ]);
+                commands.extend(self.status_notes());
+                (commands, None)
+            }
+            UiMode::EditingPattern => (
+                hints(&[
+                    "Ctrl+C: Quit",
+                    "↑↓ : scroll",
+                    "ENTER: select process",
+                    "ESC: exit search mode",
+                    "Ctrl+F: toggle literal",
+                    "Ctrl+W: toggle whole words",
+                    "Ctrl+Z: toggle fuzzy",
+                ]),
+                Some(format!(
+                    "type {}: {}▌{}",
+                    self.pattern_label(),
+                    self.pattern_text(),
+                    if self.pattern_text_is_invalid() {
+                        " (invalid regex)"
+                    } else {
+                        ""
+                    }
+                )),
+            ),
+            UiMode::EditingPid => (
+                hints(&["Ctrl+C: Quit", "ENTER: jump", "ESC: cancel"]),
+                Some(format!("jump to pid: {}▌", self.pid_input)),
+            ),
+            UiMode::EditingThreshold(threshold) => (
+                hints(&["Ctrl+C: Quit", "ENTER: apply", "ESC: cancel"]),
+                Some(match threshold {
+                    Threshold::Cpu => format!("minimum cpu (%): {}▌", self.threshold_input),
+                    Threshold::Ram => format!("minimum ram (MB): {}▌", self.threshold_input),
+                }),
+            ),
+            UiMode::ProcessSelected(_pid) => {
+                let mut commands: Vec<String> = hints(&[
+                    "Ctrl+C: Quit",
+                    "↑↓ : scroll",
+                    "t: SIGTERM process",
+                    "K: SIGKILL process",
+                    "h: SIGHUP",
+                    "i: SIGINT",
+                    "s: SIGSTOP",
+                    "c: SIGCONT",
+                    "x/X: SIGTERM/SIGKILL subtree",
+                    "ESC: unselect",
+                    "ENTER: select other",
+                ]);
+                commands.extend(self.status_notes());
+                (commands, None)
+            }
+            UiMode::ConfirmingSignal {
+                pid,
+                signal,
+                descendants,
+            } => {
+                let name = self
+                    .forest
+                    .iter()
+                    .find(|p| p.id() == pid)
+                    .map(|p| p.name.as_str())
+                    .unwrap_or_default();
+                let question = if let Some(descendants) = descendants {
+                    format!(
+                        "Send {signal} to {name} ({pid}) and its {descendants} descendants? [y/N]"
+                    )
+                } else {
+                    format!("Send {signal} to {name} ({pid})? [y/N]")
+                };
+                (vec![question], None)
+            }
+            UiMode::ConfirmingSignalToMatches => (
+                vec![format!(
+                    "Send SIGTERM to all {} processes matching '{}'? [y/N]",
+                    self.matching_pids().len(),
+                    self.pattern.as_str()
+                )],
+                None,
+            ),
+            UiMode::Help => (hints(&["any key: close help"]), None),
+        }
+    }
+
+    fn handle_signal_result(&mut self, result: nix::Result<()>) -> R<()> {
+        match result {
+            Ok(()) => {}
+            Err(Errno::EPERM) => {
+                self.error_state = Some("missing permissions to send signal".to_string());
+            }
+            Err(Errno::ESRCH) => {
+                self.error_state = Some("process no longer exists".to_string());
+                self.refresh_watcher();
+            }
+            Err(e) => Err(e)?,
+        }
+        Ok(())
+    }
+
+    /// Rows the process list keeps at least when the details pane is shown. In smaller terminals
+    /// the pane is left out.
+    const MIN_LIST_HEIGHT: u16 = 2;
+
+    /// The details of the process selected with Enter, see `Process::details`. Empty if no
+    /// process is selected.
+    fn selected_details(&self) -> Vec<Line<'static>> {
+        let UiMode::ProcessSelected(pid) = self.ui_mode else {
+            return Vec::new();
+        };
+        self.forest
+            .iter()
+            .find(|process| process.id() == pid)
+            .map(|process| process.details(self.process_watcher.cwd(pid).as_deref()))
+            .unwrap_or_default()
+    }
+
+    /// Renders the details below a rule in the first row of `area`.
+    fn render_details(&self, details: Paragraph<'static>, area: Rect, buffer: &mut Buffer) {
+        let rule = self
+            .args
+            .tree_style
+            .header_rule()
+            .repeat(usize::from(area.width));
+        buffer.set_line(
+            area.x,
+            area.y,
+            &Line::from(Span::styled(rule, self.args.theme.dimmed())),
+            area.width,
+        );
+        details.render(
+            Rect {
+                y: area.y + 1,
+                height: area.height - 1,
+                ..area
+            },
+            buffer,
+        );
+    }
+}
+
+impl tui_app::TuiApp for TreetopApp {
+    fn update(&mut self, event: KeyEvent) -> R<UpdateResult> {
+        self.error_state = None;
+        if let Some((_, Termination::Terminated)) = self.termination {
+            self.termination = None;
+        }
+        let typing_pattern = self.ui_mode == UiMode::EditingPattern
+            && matches!(
+                (event.modifiers, event.code),
+                (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(_))
+                    | (KeyModifiers::NONE, KeyCode::Backspace)
+            );
+        if !typing_pattern {
+            self.apply_pending_pattern();
+        }
+        let mut handled = true;
+        #[allow(clippy::match_same_arms)]
+        match (event.modifiers, self.ui_mode, event.code) {
+            (KeyModifiers::CONTROL, _, KeyCode::Char('c'))
+            | (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('q')) => {
+                return Ok(UpdateResult::Exit);
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Esc) if self.args.esc_quits => {
+                return Ok(UpdateResult::Exit);
+            }
+            (_, UiMode::Help, _) => {
+                self.ui_mode = UiMode::Normal;
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('?')) => {
+                self.ui_mode = UiMode::Help;
+            }
+            (
+                _,
+                UiMode::ConfirmingSignal {
+                    pid,
+                    signal,
+                    descendants,
+                },
+                _,
+            ) => {
+                self.confirm_signal(event, pid, signal, descendants)?;
+            }
+            (_, UiMode::ConfirmingSignalToMatches, _) => {
+                self.confirm_signal_to_matches(event)?;
+            }
+            (KeyModifiers::NONE, _, KeyCode::Up)
+            | (
+                KeyModifiers::NONE,
+                UiMode::Normal | UiMode::ProcessSelected(_),
+                KeyCode::Char('k'),
+            ) => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_sub(1),
+                ));
+            }
+            (KeyModifiers::NONE, _, KeyCode::PageUp) => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_sub(20),
+                ));
+            }
+            (KeyModifiers::NONE, _, KeyCode::Down)
+            | (
+                KeyModifiers::NONE,
+                UiMode::Normal | UiMode::ProcessSelected(_),
+                KeyCode::Char('j'),
+            ) => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_add(1),
+                ));
+            }
+            (KeyModifiers::NONE, _, KeyCode::PageDown) => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_add(20),
+                ));
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('g')) => {
+                self.list_state.select(Some(0));
+            }
+            (KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('G')) => {
+                let rows = self
+                    .forest
+                    .render_forest_prefixes(self.args.tree_style)
+                    .len();
+                self.list_state.select(Some(rows.saturating_sub(1)));
+            }
+            (KeyModifiers::NONE, UiMode::EditingPattern, KeyCode::Enter) => {
+                self.ui_mode = UiMode::Normal;
+            }
+            (KeyModifiers::NONE, UiMode::EditingPid, KeyCode::Enter) => {
+                self.ui_mode = UiMode::Normal;
+                self.apply_pid_input();
+            }
+            (KeyModifiers::NONE, UiMode::EditingThreshold(threshold), KeyCode::Enter) => {
+                self.apply_threshold_input(threshold);
+                self.ui_mode = UiMode::Normal;
+            }
+            (KeyModifiers::NONE, _, KeyCode::Enter) => {
+                if let Some(pid) = self.selected_pid() {
+                    if self.jump_to(pid) {
+                        self.history.push(pid);
+                    }
+                }
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Left) => {
+                self.scroll_horizontally(false);
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Right) => {
+                self.scroll_horizontally(true);
+            }
+            (KeyModifiers::ALT, _, KeyCode::Left) => {
+                if let Some(pid) = self.history.back() {
+                    self.jump_to(pid);
+                }
+            }
+            (KeyModifiers::ALT, _, KeyCode::Right) => {
+                if let Some(pid) = self.history.forward() {
+                    self.jump_to(pid);
+                }
+            }
+            (KeyModifiers::CONTROL, _, KeyCode::Char('f')) => {
+                self.pattern.toggle_literal();
+            }
+            (KeyModifiers::CONTROL, _, KeyCode::Char('w')) => {
+                self.pattern.toggle_whole_words();
+            }
+            (KeyModifiers::CONTROL, _, KeyCode::Char('z')) => {
+                self.pattern.toggle_fuzzy();
+            }
+            (
+                KeyModifiers::NONE,
+                UiMode::Normal | UiMode::ProcessSelected(_),
+                KeyCode::Char('/'),
+            ) => {
+                self.ui_mode = UiMode::EditingPattern;
+            }
+            (KeyModifiers::NONE, _, KeyCode::Tab) => {
+                self.sort_column = self.sort_column.next(&self.args.sort_columns);
+                self.descending = self.sort_column.descending_by_default();
+            }
+            // terminals report Shift+Tab as BackTab
+            (KeyModifiers::SHIFT, _, KeyCode::Tab) | (_, _, KeyCode::BackTab) => {
+                self.sort_column = self.sort_column.prev(&self.args.sort_columns);
+                self.descending = self.sort_column.descending_by_default();
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('r')) => {
+                self.descending = !self.descending;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('z')) => {
+                self.sort_roots_by_size = !self.sort_roots_by_size;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('m'))
+            | (KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('M')) => {
+                let threshold = if event.code == KeyCode::Char('m') {
+                    Threshold::Cpu
+                } else {
+                    Threshold::Ram
+                };
+                self.threshold_input = match threshold {
+                    Threshold::Cpu => self.min_cpu.map(|x| x.to_string()),
+                    Threshold::Ram => self.min_ram_mb.map(|x| x.to_string()),
+                }
+                .unwrap_or_default();
+                self.ui_mode = UiMode::EditingThreshold(threshold);
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char(':')) => {
+                self.pid_input.clear();
+                self.ui_mode = UiMode::EditingPid;
+            }
+            (KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('T')) => {
+                self.request_signal_to_matches()?;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('a')) => {
+                self.aggregate = !self.aggregate;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('o')) => {
+                self.outline = !self.outline;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('f')) => {
+                self.flat = !self.flat;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('v')) => {
+                self.list_view = !self.list_view;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('l')) => {
+                self.only_leaves = !self.only_leaves;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('h')) => {
+                self.highlight_only = !self.highlight_only;
+            }
+            (
+                KeyModifiers::NONE,
+                UiMode::Normal | UiMode::ProcessSelected(_),
+                KeyCode::Char('y'),
+            ) => {
+                self.copy_selected_command();
+            }
+            (KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('H')) => {
+                self.threads = !self.threads;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('p')) => {
+                self.full_path = !self.full_path;
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('>')) => {
+                if let Some(selected) = self.selected_pid() {
+                    if self.zoom.last().map(|(root, _)| *root) != Some(selected) {
+                        let name = self
+                            .forest
+                            .iter()
+                            .find(|p| p.id() == selected)
+                            .map(|p| p.name.clone())
+                            .unwrap_or_default();
+                        self.zoom.push((selected, name));
+                        self.update_processes();
+                        self.follow_selection(selected);
+                    }
+                }
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('<')) => {
+                if let Some((root, _)) = self.zoom.pop() {
+                    self.update_processes();
+                    self.follow_selection(root);
+                }
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, UiMode::Normal, KeyCode::Char('+')) => {
+                self.max_depth = match self.max_depth {
+                    Some(max_depth) if max_depth + 1 < self.tree_depth => Some(max_depth + 1),
+                    _ => None,
+                };
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('-')) => {
+                let max_depth = self.max_depth.unwrap_or(self.tree_depth);
+                self.max_depth = Some(max_depth.saturating_sub(1).max(1));
+            }
+            (KeyModifiers::NONE, _, KeyCode::F(5)) => {
+                self.refresh();
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char(' ')) => {
+                self.paused = !self.paused;
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('e')) => {
+                if let Some(pid) = self.selected_pid() {
+                    if !self.collapsed.remove(&pid) {
+                        self.collapsed.insert(pid);
+                    }
+                }
+            }
+            (KeyModifiers::NONE, UiMode::Normal, KeyCode::Char('c')) => {
+                if self.collapsed.is_empty() {
+                    self.collapsed = self
+                        .forest
+                        .iter()
+                        .filter(|p| p.cpu() < self.args.collapse_below)
+                        .map(Node::id)
+                        .collect();
+                } else {
+                    self.collapsed.clear();
+                }
+            }
+
+            // mode specific actions
+            (
+                KeyModifiers::NONE,
+                UiMode::EditingPattern
+                | UiMode::EditingThreshold(_)
+                | UiMode::EditingPid
+                | UiMode::ProcessSelected(_),
+                KeyCode::Esc,
+            ) => {
+                self.ui_mode = UiMode::Normal;
+            }
+            // crossterm reports uppercase characters with SHIFT
+            (
+                KeyModifiers::NONE | KeyModifiers::SHIFT,
+                UiMode::EditingPattern,
+                KeyCode::Char(key),
+            ) if key.is_ascii() => {
+                let mut pattern = self.pattern_text().to_string();
+                pattern.push(key);
+                self.pending_pattern = Some(pattern);
+                return Ok(UpdateResult::Debounce);
+            }
+            (KeyModifiers::NONE, UiMode::EditingPattern, KeyCode::Backspace) => {
+                let mut pattern = self.pattern_text().to_string();
+                pattern.pop();
+                self.pending_pattern = Some(pattern);
+                return Ok(UpdateResult::Debounce);
+            }
+            (KeyModifiers::NONE, UiMode::EditingThreshold(_), KeyCode::Char(key))
+                if key.is_ascii_digit() || key == '.' =>
+            {
+                self.threshold_input.push(key);
+            }
+            (KeyModifiers::NONE, UiMode::EditingThreshold(_), KeyCode::Backspace) => {
+                self.threshold_input.pop();
+            }
+            (KeyModifiers::NONE, UiMode::EditingPid, KeyCode::Char(key))
+                if key.is_ascii_digit() =>
+            {
+                self.pid_input.push(key);
+            }
+            (KeyModifiers::NONE, UiMode::EditingPid, KeyCode::Backspace) => {
+                self.pid_input.pop();
+            }
+            (KeyModifiers::NONE, UiMode::ProcessSelected(pid), KeyCode::Char(char))
+                if signal_for_key(char).is_some() =>
+            {
+                if let Some(signal) = signal_for_key(char) {
+                    self.request_signal(pid, signal, false)?;
+                }
+            }
+            (KeyModifiers::NONE, UiMode::ProcessSelected(pid), KeyCode::Char('x')) => {
+                self.request_signal(pid, nix::sys::signal::Signal::SIGTERM, true)?;
+            }
+            (KeyModifiers::SHIFT, UiMode::ProcessSelected(pid), KeyCode::Char('X')) => {
+                self.request_signal(pid, nix::sys::signal::Signal::SIGKILL, true)?;
+            }
+            // a process that survived a signal is only offered to be killed while it's selected,
+            // see `forget_unselected_termination`
+            (KeyModifiers::SHIFT, UiMode::ProcessSelected(pid), KeyCode::Char('K'))
+            | (KeyModifiers::CONTROL, UiMode::ProcessSelected(pid), KeyCode::Char('k')) => {
+                self.request_signal(pid, nix::sys::signal::Signal::SIGKILL, false)?;
+            }
+            _ => handled = false,
+        }
+        self.forget_unselected_termination();
+        self.update_processes();
+        self.reset_horizontal_scroll_on_selection_change();
+        Ok(if handled {
+            UpdateResult::Continue
+        } else {
+            UpdateResult::Ignored
+        })
+    }
+
+    fn debounced_update(&mut self) {
+        self.apply_pending_pattern();
+        self.update_processes();
+    }
+
+    fn mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(_) => {
+                if let Some(index) = self.row_at(event.row) {
+                    self.list_state.select(Some(index));
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_sub(1),
+                ));
+            }
+            MouseEventKind::ScrollDown => {
+                self.list_state.select(Some(
+                    self.list_state.selected().unwrap_or(0).saturating_add(1),
+                ));
+            }
+            _ => {}
+        }
+        self.reset_horizontal_scroll_on_selection_change();
+    }
+
+    fn render(&mut self, area: Rect, buffer: &mut Buffer) {
+        if self.ui_mode == UiMode::Help {
+            help::render(area, buffer);
+            return;
+        }
+        let summary_height = self.render_summary(area, buffer);
+        let header_area = Rect {
+            y: area.y + summary_height,
+            height: area.height.saturating_sub(summary_height),
+            ..area
+        };
+        let pid_width = Process::pid_width(self.forest.iter());
+        let header_height = summary_height
+            + if header_area.is_empty() {
+                0
+            } else {
+                Process::render_header(
+                    header_area,
+                    &self.args,
+                    pid_width,
+                    self.sort_column,
+                    self.descending,
+                    buffer,
+                )
+            };
+        let list_rect = Rect {
+            x: area.x,
+            y: area.y + header_height,
+            width: area.width,
+            height: area
+                .height
+                .saturating_sub(header_height + 1)
+                .saturating_sub(if self.error_state.is_some() { 1 } else { 0 }),
+        };
+        let details = self.selected_details();
+        let has_details = !details.is_empty();
+        // long commands are wrapped, so the pane can be higher than the number of lines
+        let details = Paragraph::new(details).wrap(Wrap { trim: false });
+        let details_height = u16::try_from(details.line_count(area.width) + 1).unwrap_or(u16::MAX);
+        let list_rect = if has_details
+            && list_rect.height >= details_height.saturating_add(TreetopApp::MIN_LIST_HEIGHT)
+        {
+            let list_rect = Rect {
+                height: list_rect.height - details_height,
+                ..list_rect
+            };
+            self.render_details(
+                details,
+                Rect {
+                    y: list_rect.y + list_rect.height,
+                    height: details_height,
+                    ..list_rect
+                },
+                buffer,
+            );
+            list_rect
+        } else {
+            list_rect
+        };
+        self.list_area = list_rect;
+        let list = self.forest.render_forest_prefixes(self.args.tree_style);
+        normalize_list_state(&mut self.list_state, &list, list_rect);
+        let total_memory = self.process_watcher.total_memory();
+        let ancestors = self
+            .list_state
+            .selected()
+            .and_then(|selected| list.get(selected))
+            .map(|row| self.forest.ancestor_ids(row.1.id()))
+            .unwrap_or_default();
+        let theme = self.args.theme;
+        let tree_lines = list.iter().enumerate().map(|(i, x)| {
+            let mut line = Line::default();
+            for span in
+                x.1.table_data(&self.args.columns, pid_width, total_memory, theme)
+            {
+                line.push_span(span);
+            }
+            line.push_span(" ");
+            line.push_span(Span::styled(
+                self.args.tree_style.column_separator(),
+                theme.dimmed(),
+            ));
+            let is_selected = if let UiMode::ProcessSelected(pid)
+            | UiMode::ConfirmingSignal { pid, .. } = self.ui_mode
+            {
+                pid == x.1.id()
+            } else {
+                false
+            };
+            // without colors the selected process can't be shown in reverse video
+            line.push_span(if self.args.no_color && is_selected {
+                "*"
+            } else {
+                " "
+            });
+            line.push_span(if self.list_state.selected() == Some(i) {
+                "▶ "
+            } else {
+                "  "
+            });
+            let is_ancestor = ancestors.contains(&x.1.id());
+            line.push_span(if is_ancestor {
+                Span::styled(x.0.as_str(), theme.tree_prefix().bold())
+            } else {
+                Span::styled(x.0.as_str(), theme.tree_prefix())
+            });
+            let base_style = if is_selected {
+                theme.selected_process()
+            } else {
+                Style::new().not_reversed()
+            };
+            let base_style = if is_ancestor {
+                base_style.bold()
+            } else {
+                base_style
+            };
+            let mut highlights = vec![(&self.pattern, theme.pattern_match())];
+            if let Some(highlight) = &self.highlight {
+                highlights.push((highlight, theme.secondary_match()));
+            }
+            let command = x.1.to_string();
+            let offset = if self.list_state.selected() == Some(i) {
+                self.horizontal_offset(x.1.id())
+            } else {
+                0
+            };
+            if offset > 0 {
+                line.push_span(Span::styled("…", theme.dimmed()));
+            }
+            for span in style_spans(skip_width(&command, offset), base_style, &highlights) {
+                line.push_span(span);
+            }
+            if self.outline && x.2 > 0 {
+                line.push_span(Span::styled(format!(" [{}]", x.2), theme.dimmed()));
+            }
+            truncate_line(line, usize::from(list_rect.width))
+        });
+        StatefulWidget::render(
+            List::new(tree_lines),
+            list_rect,
+            buffer,
+            &mut self.list_state,
+        );
+        if list.is_empty() {
+            let message = if self.pattern.as_str().is_empty() {
+                "No processes"
+            } else {
+                "No matching processes"
+            };
+            Paragraph::new(message)
+                .centered()
+                .style(theme.dimmed())
+                .render(
+                    Rect {
+                        y: list_rect.y + list_rect.height / 2,
+                        height: list_rect.height.min(1),
+                        ..list_rect
+                    },
+                    buffer,
+                );
+        }
+        if let Some(error) = &self.error_state {
+            Paragraph::new(format!("Error: {error}"))
+                .red()
+                .bold()
+                .reversed()
+                .render(
+                    Rect {
+                        x: area.x,
+                        y: area.height.saturating_sub(2),
+                        width: area.width,
+                        height: 1,
+                    },
+                    buffer,
+                );
+        }
+        {
+            let (hints, input) = self.status_bar_parts();
+            let input = input.map(|input| {
+                if self.ui_mode == UiMode::EditingPattern && self.pattern_text_is_invalid() {
+                    input.red()
+                } else {
+                    input.into()
+                }
+            });
+            let status_bar = fit_status_bar(&hints, input.as_ref(), usize::from(area.width));
+            let mut status_bar = Paragraph::new(status_bar).reversed();
+            match self.ui_mode {
+                UiMode::Normal | UiMode::Help => {}
+                UiMode::EditingPattern | UiMode::EditingThreshold(_) | UiMode::EditingPid => {
+                    status_bar = status_bar.yellow();
+                }
+                UiMode::ProcessSelected(_) => {
+                    status_bar = status_bar.blue();
+                }
+                UiMode::ConfirmingSignal { .. } | UiMode::ConfirmingSignalToMatches => {
+                    status_bar = status_bar.red();
+                }
+            }
+            status_bar.render(
+                Rect {
+                    x: area.x,
+                    y: area.height.saturating_sub(1),
+                    width: area.width,
+                    height: 1,
+                },
+                buffer,
+            );
+        }
+    }
+
+    fn no_color(&self) -> bool {
+        self.args.no_color
+    }
+
+    fn tick(&mut self) {
+        if self.paused {
+            return;
+        }
+        self.refresh();
+        self.track_termination();
+    }
+}
+
+fn signal_for_key(key: char) -> Option<nix::sys::signal::Signal> {
+    use nix::sys::signal::Signal;
+    match key {
+        't' => Some(Signal::SIGTERM),
+        'h' => Some(Signal::SIGHUP),
+        'i' => Some(Signal::SIGINT),
+        's' => Some(Signal::SIGSTOP),
+        'c' => Some(Signal::SIGCONT),
+        _ => None,
+    }
+}
+
+/// Whether a process is expected to exit after receiving `signal`, unless it handles it.
+fn ends_process(signal: nix::sys::signal::Signal) -> bool {
+    use nix::sys::signal::Signal;
+    matches!(
+        signal,
+        Signal::SIGTERM | Signal::SIGKILL | Signal::SIGHUP | Signal::SIGINT
+    )
+}
+
+/// Joins the hints and the input with " | ", dropping as many hints from the end as necessary
+/// to fit into `width`. Dropped hints are replaced by an ellipsis. The input is kept in full,
+/// unless it doesn't fit on its own, in which case its end (where the cursor is) is shown.
+fn fit_status_bar(hints: &[String], input: Option<&Span<'static>>, width: usize) -> Line<'static> {
+    let line = |shown: usize| {
+        let mut parts: Vec<Span> = hints[..shown].iter().cloned().map(Span::from).collect();
+        if shown < hints.len() {
+            parts.push("…".into());
+        }
+        parts.extend(input.cloned());
+        let mut spans = Vec::new();
+        for (i, part) in parts.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(" | ".into());
+            }
+            spans.push(part);
+        }
+        Line::from(spans)
+    };
+    if let Some(fitting) = (0..=hints.len())
+        .rev()
+        .map(line)
+        .find(|line| line.width() <= width)
+    {
+        return fitting;
+    }
+    match input {
+        Some(input) => {
+            let skipped = input.width().saturating_sub(width.saturating_sub(1));
+            Line::from(Span::styled(
+                format!("…{}", skip_width(&input.content, skipped)),
+                input.style,
+            ))
+        }
+        None => truncate_line(line(0), width),
+    }
+}
+
+fn parse_threshold<T: FromStr>(input: &str) -> Result<Option<T>, T::Err> {
+    if input.is_empty() {
+        Ok(None)
+    } else {
+        input.parse().map(Some)
+    }
+}
+
+fn normalize_list_state<T>(list_state: &mut ListState, list: &[T], rect: Rect) {
+    if let Some(ref mut selected) = list_state.selected_mut() {
+        *selected = (*selected).min(list.len().saturating_sub(1));
+    }
+    *list_state.offset_mut() = list_state
+        .offset()
+        .min(list.len().saturating_sub(rect.height.into()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::signaler::test::FakeSignaler;
+    use crate::tui_app::TuiApp;
+    use crate::usage_log::test::{FailingWriter, SharedBuffer};
+    use clap::Parser;
+    use crossterm::event::{KeyEventKind, KeyEventState, MouseButton};
+    use insta::assert_snapshot;
+    use nix::sys::signal::Signal;
+    use ratatui::buffer::Cell;
+    use ratatui::layout::Rect;
+    use ratatui::style::{Color, Modifier};
+    use ratatui::widgets::ListState;
+
+    const RECT: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 80,
+        height: 20,
+    };
+
+    #[test]
+    fn normalize_leaves_state_unmodified() {
+        let mut list_state = ListState::default().with_selected(Some(7)).with_offset(5);
+        normalize_list_state(&mut list_state, &[(); 30], RECT);
+        assert_eq!(list_state.selected(), Some(7));
+        assert_eq!(list_state.offset(), 5);
+    }
+
+    #[test]
+    fn normalize_caps_at_the_list_end() {
+        let mut list_state = ListState::default().with_selected(Some(11));
+        normalize_list_state(&mut list_state, &[(); 10], RECT);
+        assert_eq!(list_state.selected(), Some(9));
+    }
+
+    #[test]
+    fn normalize_resets_offset_to_zero_when_the_list_fits_the_area() {
+        let mut list_state = ListState::default().with_selected(Some(0)).with_offset(5);
+        normalize_list_state(&mut list_state, &[(); 10], RECT);
+        assert_eq!(list_state.offset(), 0);
+    }
+
+    #[test]
+    fn normalize_scrolls_up_when_offset_is_too_big() {
+        let mut list_state = ListState::default().with_selected(Some(0)).with_offset(25);
+        normalize_list_state(&mut list_state, &[(); 30], RECT);
+        assert_eq!(list_state.offset(), 10);
+    }
+
+    impl TreetopApp {
+        fn status_bar_text(&self) -> String {
+            let (hints, input) = self.status_bar_parts();
+            hints
+                .into_iter()
+                .chain(input)
+                .collect::<Vec<_>>()
+                .join(" | ")
+        }
+    }
+
+    fn test_app(processes: Vec<Process>) -> R<TreetopApp> {
+        test_app_with_args(processes, Args::default())
+    }
+
+    fn test_app_with_args(processes: Vec<Process>, args: Args) -> R<TreetopApp> {
+        test_app_with_signaler(processes, FakeSignaler::fake(), args)
+    }
+
+    fn test_app_with_signaler(
+        processes: Vec<Process>,
+        signaler: FakeSignaler,
+        args: Args,
+    ) -> R<TreetopApp> {
+        let mut app = TreetopApp::new(
+            ProcessWatcher::fake(processes),
+            Box::new(signaler),
+            StateFile::disabled(),
+            args,
+        )?;
+        app.tick();
+        Ok(app)
+    }
+
+    fn render_ui(app: &mut TreetopApp) -> String {
+        render_ui_underlining(app, Modifier::REVERSED)
+    }
+
+    /// Renders the app, underlining all cells that have the given modifier.
+    fn render_ui_underlining(app: &mut TreetopApp, modifier: Modifier) -> String {
+        render_ui_in(app, Rect::new(0, 0, 80, 10), modifier)
+    }
+
+    fn render_ui_in(app: &mut TreetopApp, area: Rect, modifier: Modifier) -> String {
+        let mut buffer = Buffer::filled(area, Cell::new(" "));
+        tui_app::render(app, area, &mut buffer);
+        let mut result = String::new();
+        for y in 0..area.height {
+            for x in 0..area.width {
+                let symbol = buffer[(x, y)].symbol();
+                let symbol = if buffer[(x, y)].modifier.contains(modifier) {
+                    crate::utils::test::underline(symbol)
+                } else {
+                    symbol.to_string()
+                };
+                result.push_str(&symbol);
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    fn simulate_key_press(app: &mut TreetopApp, code: KeyCode) -> R<UpdateResult> {
+        simulate_key_press_with_modifiers(app, KeyModifiers::NONE, code)
+    }
+
+    fn simulate_key_press_with_modifiers(
+        app: &mut TreetopApp,
+        modifiers: KeyModifiers,
+        code: KeyCode,
+    ) -> R<UpdateResult> {
+        app.update(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    fn set_pattern(app: &mut TreetopApp, pattern: &str) -> R<()> {
+        app.pattern = crate::regex::Regex::new(pattern)?;
+        Ok(())
+    }
+
+    #[test]
+    fn shows_a_tree_with_header_and_side_columns() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 4.0, None),
+            Process::fake(2, 3.0, Some(1)),
+            Process::fake(3, 2.0, Some(2)),
+            Process::fake(4, 1.0, None),
+            Process::fake(5, 0.0, Some(4)),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    mod persisted_state {
+        use super::*;
+
+        fn app_with_state_file(state_file: StateFile, args: Args) -> R<TreetopApp> {
+            TreetopApp::new(
+                ProcessWatcher::fake(vec![Process::fake(1, 0.0, None)]),
+                Box::new(FakeSignaler::fake()),
+                state_file,
+                args,
+            )
+        }
+
+        #[test]
+        fn restores_pattern_and_sort_column() -> R<()> {
+            let (state_file, _) = StateFile::fake(Some(
+                r#"{"pattern": "ssh", "sort_column": "ram", "descending": false}"#,
+            ));
+            let app = app_with_state_file(state_file, Args::default())?;
+            assert_eq!(app.pattern.as_str(), "ssh");
+            assert_eq!(app.sort_column, SortBy::Ram);
+            assert!(!app.descending);
+            Ok(())
+        }
+
+        #[test]
+        fn patterns_on_the_command_line_take_precedence() -> R<()> {
+            let (state_file, _) = StateFile::fake(Some(
+                r#"{"pattern": "ssh", "sort_column": "ram", "descending": false}"#,
+            ));
+            let app = app_with_state_file(state_file, Args::parse_from(["treetop", "vim"]))?;
+            assert_eq!(app.pattern.as_str(), "vim");
+            assert_eq!(app.sort_column, SortBy::Ram);
+            Ok(())
+        }
+
+        #[test]
+        fn the_sort_flag_takes_precedence() -> R<()> {
+            let (state_file, _) = StateFile::fake(Some(
+                r#"{"pattern": "", "sort_column": "ram", "descending": false}"#,
+            ));
+            let app =
+                app_with_state_file(state_file, Args::parse_from(["treetop", "--sort", "pid"]))?;
+            assert_eq!(app.sort_column, SortBy::Pid);
+            Ok(())
+        }
+
+        #[test]
+        fn ignores_sort_columns_that_are_not_enabled() -> R<()> {
+            let (state_file, _) = StateFile::fake(Some(
+                r#"{"pattern": "", "sort_column": "ram", "descending": false}"#,
+            ));
+            let app = app_with_state_file(
+                state_file,
+                Args::parse_from(["treetop", "--sort-columns", "cpu,pid"]),
+            )?;
+            assert_eq!(app.sort_column, SortBy::Pid);
+            Ok(())
+        }
+
+        #[test]
+        fn starts_with_defaults_when_the_state_file_is_corrupt() -> R<()> {
+            let (state_file, _) = StateFile::fake(Some("not json"));
+            let app = app_with_state_file(state_file, Args::default())?;
+            assert_eq!(app.pattern.as_str(), "");
+            assert_eq!(app.sort_column, SortBy::Pid);
+            Ok(())
+        }
+
+        #[test]
+        fn the_saved_state_reflects_the_current_settings() -> R<()> {
+            let (state_file, _) = StateFile::fake(None);
+            let mut app = app_with_state_file(state_file, Args::default())?;
+            set_pattern(&mut app, "foo")?;
+            simulate_key_press(&mut app, KeyCode::Tab)?;
+            app.state_file.save(&app.state())?;
+            let restored = app.state_file.load();
+            assert_eq!(
+                restored,
+                Some(State {
+                    pattern: "foo".to_string(),
+                    sort_column: SortBy::Cpu,
+                    descending: true,
+                    collapsed: BTreeSet::new(),
+                })
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn restores_collapsed_subtrees_by_command_line() -> R<()> {
+            let (state_file, file) = StateFile::fake(None);
+            let mut app = TreetopApp::new(
+                ProcessWatcher::fake(vec![
+                    Process::fake(1, 0.0, None),
+                    Process::fake(2, 0.0, Some(1))
+                        .set_name("sleep")
+                        .set_arguments(vec!["sleep", "10"]),
+                    Process::fake(3, 0.0, Some(2)),
+                ]),
+                Box::new(FakeSignaler::fake()),
+                state_file,
+                Args::default(),
+            )?;
+            app.tick();
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('e'))?;
+            app.state_file.save(&app.state())?;
+            let contents = file.borrow().clone();
+            let (state_file, _) = StateFile::fake(contents.as_deref());
+            let mut app = TreetopApp::new(
+                ProcessWatcher::fake(vec![
+                    Process::fake(10, 0.0, None),
+                    Process::fake(20, 0.0, Some(10))
+                        .set_name("sleep")
+                        .set_arguments(vec!["sleep", "10"]),
+                    Process::fake(30, 0.0, Some(20)),
+                    Process::fake(40, 0.0, Some(10))
+                        .set_name("sleep")
+                        .set_arguments(vec!["sleep", "20"]),
+                ]),
+                Box::new(FakeSignaler::fake()),
+                state_file,
+                Args::default(),
+            )?;
+            app.tick();
+            assert_eq!(app.collapsed, HashSet::from([20.into()]));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shows_ram_as_a_percentage_of_total_memory() -> R<()> {
+        let mut app = TreetopApp::new(
+            ProcessWatcher::fake(vec![
+                Process::fake(1, 0.0, None).set_ram(2_u64.pow(30)),
+                Process::fake(2, 0.0, Some(1)).set_ram(3 * 2_u64.pow(29)),
+            ])
+            .set_total_memory(4 * 2_u64.pow(30)),
+            Box::new(FakeSignaler::fake()),
+            StateFile::disabled(),
+            Args::default(),
+        )?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_a_system_summary() -> R<()> {
+        let mut app = TreetopApp::new(
+            ProcessWatcher::fake(vec![Process::fake(1, 0.0, None)])
+                .set_total_memory(16 * 2_u64.pow(30))
+                .set_used_memory(5 * 2_u64.pow(29))
+                .set_global_cpu(12.34),
+            Box::new(FakeSignaler::fake()),
+            StateFile::disabled(),
+            Args::default(),
+        )?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_uptime_of_processes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_uptime(2 * 86400 + 5 * 3600),
+            Process::fake(2, 0.0, Some(1)).set_uptime(3600 + 60),
+            Process::fake(3, 0.0, Some(1)).set_uptime(12 * 60 + 30),
+            Process::fake(4, 0.0, Some(1)).set_uptime(45),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn processes_can_be_sorted_by_uptime() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_uptime(10),
+            Process::fake(2, 0.0, None).set_uptime(30),
+            Process::fake(3, 0.0, None).set_uptime(20),
+        ])?;
+        for _ in 0..3 {
+            simulate_key_press(&mut app, KeyCode::Tab)?;
+        }
+        assert_eq!(app.sort_column, SortBy::Uptime);
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into(), 3.into(), 1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_process_owner() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_user("root"),
+            Process::fake(2, 0.0, Some(1)),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_process_status() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)).set_status(sysinfo::ProcessStatus::Zombie),
+            Process::fake(3, 0.0, Some(1)).set_status(sysinfo::ProcessStatus::Sleep),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn no_color_mode_renders_plain_text() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(2, 90.0, Some(1)).set_name("sshd"),
+                Process::fake(3, 0.0, Some(1)).set_name("cron"),
+            ],
+            Args::parse_from(["treetop", "--columns", "pid,cpu", "--no-color", "sh"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Up)?;
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buffer = Buffer::filled(area, Cell::new(" "));
+        tui_app::render(&mut app, area, &mut buffer);
+        for cell in buffer.content() {
+            assert_eq!(
+                (cell.fg, cell.bg, cell.modifier),
+                (Color::Reset, Color::Reset, Modifier::empty())
+            );
+        }
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn zombies_are_labelled_as_defunct() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None).set_arguments(vec!["/bin/init"]),
+                Process::fake(2, 0.0, Some(1))
+                    .set_arguments(vec!["/usr/bin/worker", "--jobs", "4"])
+                    .set_status(sysinfo::ProcessStatus::Zombie),
+                Process::fake(3, 0.0, Some(1)).set_arguments(vec!["/usr/bin/alpha"]),
+            ],
+            Args::parse_from(["treetop", "--columns", "pid,status", "--sort", "name"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(2.into()));
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn renders_the_tree_as_plain_text() -> R<()> {
+        let app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(1)),
+            Process::fake(5, 0.0, None),
+        ])?;
+        assert_eq!(
+            app.render_plain(),
+            [
+                "       1    0%       0MB     0s     0B     0B     -   0.0% -        R one",
+                "       2    0%       0MB     0s     0B     0B     -   0.0% -        R ├─┬ two",
+                "       3    0%       0MB     0s     0B     0B     -   0.0% -        R │ └── three",
+                "       4    0%       0MB     0s     0B     0B     -   0.0% -        R └── four",
+                "       5    0%       0MB     0s     0B     0B     -   0.0% -        R five",
+                "",
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_whether_any_processes_matched() -> R<()> {
+        let processes = || vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, Some(1))];
+        let found_matches = |args: &[&str]| -> R<bool> {
+            let args = Args::parse_from([&["treetop"], args].concat());
+            Ok(test_app_with_args(processes(), args)?.found_matches())
+        };
+        assert!(found_matches(&[])?);
+        assert!(found_matches(&["two"])?);
+        assert!(!found_matches(&["three"])?);
+        assert!(!found_matches(&["!o"])?);
+        Ok(())
+    }
+
+    #[test]
+    fn plain_text_rendering_respects_the_pattern() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, None),
+        ])?;
+        set_pattern(&mut app, "two")?;
+        app.tick();
+        let plain = app.render_plain();
+        assert!(plain.contains("two"));
+        assert!(!plain.contains("three"));
+        Ok(())
+    }
+
+    #[test]
+    fn serializes_the_filtered_forest_to_json() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None).set_arguments(vec!["/bin/one", "--flag"]),
+            Process::fake(2, 2.0, Some(1)),
+            Process::fake(3, 4.0, None),
+        ])?;
+        set_pattern(&mut app, "two")?;
+        app.tick();
+        assert_eq!(
+            serde_json::to_value(&app.forest)?,
+            serde_json::json!([
+                {
+                    "pid": 1,
+                    "name": "one",
+                    "arguments": ["/bin/one", "--flag"],
+                    "cpu": 3.0,
+                    "ram": 0,
+                    "children": [
+                        {
+                            "pid": 2,
+                            "name": "two",
+                            "arguments": [],
+                            "cpu": 2.0,
+                            "ram": 0,
+                            "children": [],
+                        }
+                    ],
+                }
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn processes_get_sorted_by_pid() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, None),
+            Process::fake(3, 4.0, None),
+            Process::fake(4, 3.0, None),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn processes_can_be_sorted_by_cpu() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, None),
+            Process::fake(3, 4.0, None),
+            Process::fake(4, 3.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn processes_can_be_sorted_by_cpu_ascending() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, None),
+            Process::fake(3, 4.0, None),
+            Process::fake(4, 3.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        simulate_key_press(&mut app, KeyCode::Char('r'))?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn reversed_sorting_breaks_ties_by_descending_pid() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 1.0, None),
+            Process::fake(3, 2.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![3.into(), 2.into(), 1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn processes_can_be_sorted_by_name() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_name("alpha"),
+            Process::fake(2, 0.0, None).set_name("zeta"),
+            Process::fake(3, 0.0, None).set_name("mike"),
+        ])?;
+        for _ in 0..7 {
+            simulate_key_press(&mut app, KeyCode::Tab)?;
+        }
+        assert_eq!(app.sort_column, SortBy::Name);
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn sorting_by_name_uses_the_shown_names() -> R<()> {
+        let app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None)
+                    .set_name("a-login-shell")
+                    .set_arguments(vec!["/usr/bin/zsh"]),
+                Process::fake(2, 0.0, None)
+                    .set_name("z-script")
+                    .set_arguments(vec!["/bin/bash", "script.sh"]),
+            ],
+            Args::parse_from(["treetop", "--sort", "name"]),
+        )?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into(), 1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shift_tab_cycles_sort_columns_backwards() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        assert_eq!(app.sort_column, SortBy::Pid);
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::BackTab)?;
+        assert_eq!(app.sort_column, SortBy::Name);
+        assert!(!app.descending);
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Fds);
+        assert!(app.descending);
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Name);
+        Ok(())
+    }
+
+    #[test]
+    fn shift_tab_skips_columns_that_are_not_enabled() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)],
+            Args::parse_from(["treetop", "--sort-columns", "pid,cpu,ram"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::BackTab)?;
+        assert_eq!(app.sort_column, SortBy::Ram);
+        simulate_key_press(&mut app, KeyCode::BackTab)?;
+        assert_eq!(app.sort_column, SortBy::Cpu);
+        Ok(())
+    }
+
+    #[test]
+    fn processes_can_be_sorted_by_disk_writes() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None).set_disk_rates(2048, 0),
+                Process::fake(2, 0.0, None).set_disk_rates(0, 5 * 2_u64.pow(20)),
+                Process::fake(3, 0.0, None).set_disk_rates(0, 300),
+            ],
+            Args::parse_from(["treetop", "--sort", "disk-write"]),
+        )?;
+        assert_eq!(app.sort_column, SortBy::DiskWrite);
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn tab_skips_disabled_sort_columns() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)],
+            Args::parse_from(["treetop", "--sort-columns", "pid,cpu"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Cpu);
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Pid);
+        Ok(())
+    }
+
+    #[test]
+    fn initial_sort_column_falls_back_to_the_first_enabled_column() -> R<()> {
+        let app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)],
+            Args::parse_from(["treetop", "--sort-columns", "ram,name"]),
+        )?;
+        assert_eq!(app.sort_column, SortBy::Ram);
+        assert!(app.descending);
+        Ok(())
+    }
+
+    #[test]
+    fn the_initial_sort_column_can_be_passed_on_the_command_line() -> R<()> {
+        let app = test_app_with_args(
+            vec![
+                Process::fake(1, 1.0, None),
+                Process::fake(2, 3.0, None),
+                Process::fake(3, 2.0, None),
+            ],
+            Args::parse_from(["treetop", "--sort", "cpu"]),
+        )?;
+        assert_eq!(app.sort_column, SortBy::Cpu);
+        assert!(app.descending);
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into(), 3.into(), 1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn roots_can_be_sorted_by_subtree_size() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(2)),
+            Process::fake(5, 0.0, None),
+            Process::fake(6, 0.0, Some(5)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('z'))?;
+        assert_eq!(
+            app.forest.roots().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into(), 5.into(), 1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rounded_tree_style() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(2, 0.0, Some(1)),
+                Process::fake(3, 0.0, Some(2)),
+                Process::fake(4, 0.0, Some(1)),
+            ],
+            Args::parse_from(["treetop", "--tree-style", "rounded"]),
+        )?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_the_configured_columns_in_order() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 12.0, None),
+                Process::fake(2, 3.0, Some(1)),
+                Process::fake(3, 80.0, Some(2)),
+            ],
+            Args::parse_from(["treetop", "--columns", "cpu,pid"]),
+        )?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn tab_skips_hidden_columns() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)],
+            Args::parse_from(["treetop", "--columns", "cpu,pid"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Cpu);
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        assert_eq!(app.sort_column, SortBy::Name);
+        Ok(())
+    }
+
+    #[test]
+    fn emphasizes_the_tree_prefixes_of_the_selected_process_ancestors() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(1)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        assert_snapshot!(render_ui_underlining(&mut app, Modifier::BOLD));
+        Ok(())
+    }
+
+    #[test]
+    fn ascii_tree_style_matches_the_unicode_layout() -> R<()> {
+        let render = |args: &[&str]| -> R<String> {
+            let mut app = test_app_with_args(
+                vec![
+                    Process::fake(1, 0.0, None),
+                    Process::fake(2, 0.0, Some(1)),
+                    Process::fake(3, 0.0, Some(2)),
+                    Process::fake(4, 0.0, Some(1)),
+                ],
+                Args::parse_from(args),
+            )?;
+            Ok(render_ui(&mut app))
+        };
+        let unicode = render(&["treetop"])?;
+        let ascii = render(&["treetop", "--tree-style", "ascii"])?;
+        assert_snapshot!(format!("{unicode}\n{ascii}"));
+        Ok(())
+    }
+
+    #[test]
+    fn limiting_the_depth_rolls_up_hidden_processes() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 1.0, None),
+                Process::fake(2, 2.0, Some(1)),
+                Process::fake(3, 3.0, Some(2)),
+                Process::fake(4, 4.0, Some(3)),
+                Process::fake(5, 5.0, Some(1)),
+            ],
+            Args::parse_from(["treetop", "--max-depth", "2"]),
+        )?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn max_depth_can_be_adjusted_at_runtime() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(2)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('-'))?;
+        assert_eq!(app.max_depth, Some(2));
+        simulate_key_press(&mut app, KeyCode::Char('-'))?;
+        simulate_key_press(&mut app, KeyCode::Char('-'))?;
+        assert_eq!(app.max_depth, Some(1));
+        assert_eq!(app.forest.iter().count(), 1);
+        simulate_key_press(&mut app, KeyCode::Char('+'))?;
+        assert_eq!(app.max_depth, Some(2));
+        simulate_key_press(&mut app, KeyCode::Char('+'))?;
+        assert_eq!(app.max_depth, None);
+        assert_eq!(app.forest.iter().count(), 3);
+        Ok(())
+    }
+
+    mod zoom {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(2, 0.0, Some(1)).set_name("sshd"),
+                Process::fake(3, 0.0, Some(2)).set_name("bash"),
+                Process::fake(4, 0.0, Some(3)).set_name("vim"),
+                Process::fake(5, 0.0, Some(2)).set_name("bash"),
+                Process::fake(6, 0.0, Some(1)).set_name("cron"),
+            ]
+        }
+
+        #[test]
+        fn shows_only_the_subtree_of_the_selected_process() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--columns", "pid"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('>'))?;
+            assert_snapshot!(render_ui(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn zooming_in_twice_and_back_out() -> R<()> {
+            let mut app = test_app(processes())?;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('>'))?;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('>'))?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![3.into(), 4.into()]
+            );
+            assert!(app
+                .status_notes()
+                .contains(&"zoom: sshd (2) › bash (3)".to_string()));
+            simulate_key_press(&mut app, KeyCode::Char('<'))?;
+            assert_eq!(app.forest.iter().count(), 4);
+            assert_eq!(app.selected_pid(), Some(3.into()));
+            simulate_key_press(&mut app, KeyCode::Char('<'))?;
+            assert_eq!(app.forest.iter().count(), 6);
+            assert_eq!(app.selected_pid(), Some(2.into()));
+            assert!(!app
+                .status_notes()
+                .iter()
+                .any(|note| note.starts_with("zoom")));
+            Ok(())
+        }
+
+        #[test]
+        fn zooms_back_out_when_the_root_exits() -> R<()> {
+            let mut app = test_app(processes())?;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('>'))?;
+            app.process_watcher = ProcessWatcher::fake(vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(6, 0.0, Some(1)).set_name("cron"),
+            ]);
+            app.tick();
+            assert_eq!(app.forest.iter().count(), 2);
+            assert!(app.zoom.is_empty());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn toggling_aggregation_of_descendant_usage() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None).set_ram(2_u64.pow(20)),
+            Process::fake(2, 40.0, Some(1)).set_ram(300 * 2_u64.pow(20)),
+            Process::fake(3, 50.0, Some(1)).set_ram(500 * 2_u64.pow(20)),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('a'))?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn more_complicated_tree() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, Some(1)),
+            Process::fake(3, 3.0, Some(2)),
+            Process::fake(4, 4.0, Some(1)),
+            Process::fake(5, 5.0, Some(4)),
+            Process::fake(6, 5.0, Some(4)),
+            Process::fake(7, 5.0, Some(6)),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn filtering() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, Some(1)),
+            Process::fake(3, 3.0, Some(2)),
+            Process::fake(4, 4.0, Some(1)),
+            Process::fake(5, 5.0, Some(4)),
+            Process::fake(6, 5.0, Some(4)),
+            Process::fake(7, 5.0, Some(6)),
+        ])?;
+        set_pattern(&mut app, "four")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn negated_patterns_hide_matching_processes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_name("kthreadd"),
+            Process::fake(2, 0.0, Some(1)).set_name("kworker/0:1"),
+            Process::fake(3, 0.0, Some(1)).set_name("kworker/1:0"),
+            Process::fake(4, 0.0, None).set_name("kworker/u8:2"),
+            Process::fake(5, 0.0, Some(4)).set_name("bash"),
+            Process::fake(6, 0.0, None).set_name("sshd"),
+        ])?;
+        set_pattern(&mut app, "!kworker")?;
+        app.tick();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 4.into(), 5.into(), 6.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filtering_with_regexes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(1)),
+            Process::fake(4, 0.0, Some(1)),
+        ])?;
+        set_pattern(&mut app, "two|three")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn filtering_by_pid() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, None),
+        ])?;
+        set_pattern(&mut app, "2")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn filtering_by_process_arguments() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
+            Process::fake(2, 0.0, None).set_arguments(vec!["bar"]),
+            Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
+        ])?;
+        set_pattern(&mut app, "bar")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn filters_out_itself_by_default() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
+            Process::fake(std::process::id() as usize, 0.0, None).set_arguments(vec!["bar"]),
+            Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
+        ])?;
+        set_pattern(&mut app, "bar")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn parent_patterns_match_the_children_of_matching_processes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(1)),
+            Process::fake(5, 0.0, None),
+            Process::fake(6, 0.0, Some(5)),
+        ])?;
+        set_pattern(&mut app, "parent:one")?;
+        app.tick();
+        let treetop_pid = sysinfo::Pid::from_u32(process::id());
+        let matches: Vec<sysinfo::Pid> = app
+            .forest
+            .iter()
+            .filter(|p| p.is_match(&app.pattern, treetop_pid, &app.args))
+            .map(Node::id)
+            .collect();
+        assert_eq!(matches, vec![2.into(), 4.into()]);
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into(), 4.into()]
+        );
+        set_pattern(&mut app, "!parent:one")?;
+        app.tick();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into(), 5.into(), 6.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn prefixed_terms_can_be_combined_with_other_terms() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, None),
+            Process::fake(4, 0.0, None).set_user("root"),
+            Process::fake(5, 0.0, None),
+            Process::fake(15, 0.0, None),
+        ])?;
+        let shown = |app: &TreetopApp| app.forest.iter().map(Node::id).collect::<Vec<_>>();
+        set_pattern(&mut app, "three,parent:one")?;
+        app.tick();
+        assert_eq!(shown(&app), vec![1.into(), 2.into(), 3.into()]);
+        set_pattern(&mut app, "three,pid:5-10")?;
+        app.tick();
+        assert_eq!(shown(&app), vec![3.into(), 5.into()]);
+        set_pattern(&mut app, "three,user:root")?;
+        app.tick();
+        assert_eq!(shown(&app), vec![3.into(), 4.into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn lists_the_processes_using_the_most_cpu() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 1.0, None),
+                Process::fake(2, 5.0, Some(1)),
+                Process::fake(3, 3.0, Some(2)).set_name("foo"),
+                Process::fake(4, 4.0, Some(1)).set_name("foo"),
+                Process::fake(5, 2.0, None).set_name("foo"),
+            ],
+            Args::parse_from(["treetop", "--no-aggregate"]),
+        )?;
+        let top = |app: &TreetopApp, count| {
+            app.top_processes(count)
+                .into_iter()
+                .map(Node::id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(top(&app, 3), vec![2.into(), 4.into(), 3.into()]);
+        assert_eq!(top(&app, 10).len(), 5);
+        set_pattern(&mut app, "foo")?;
+        app.tick();
+        assert_eq!(top(&app, 2), vec![4.into(), 3.into()]);
+        set_pattern(&mut app, "!foo")?;
+        app.tick();
+        assert_eq!(top(&app, 5), vec![2.into(), 1.into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn counts_total_and_matched_processes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)).set_name("foo"),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(1)).set_name("foo"),
+            Process::fake(5, 0.0, None),
+        ])?;
+        assert_eq!((app.matched_processes, app.total_processes), (5, 5));
+        set_pattern(&mut app, "foo")?;
+        app.tick();
+        assert_eq!((app.matched_processes, app.total_processes), (4, 5));
+        assert_eq!(
+            render_ui(&mut app).lines().next().map(str::trim_end),
+            Some("cpu: 0.0% | ram: 0MB / 16,384MB | processes: 4/5")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shows_a_note_when_hiding_itself() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
+            Process::fake(std::process::id() as usize, 0.0, None).set_arguments(vec!["bar"]),
+        ])?;
+        assert_eq!(app.hidden_self_matches, 0);
+        set_pattern(&mut app, "bar")?;
+        app.tick();
+        assert_eq!(app.hidden_self_matches, 1);
+        assert!(app
+            .status_notes()
+            .contains(&"1 self-match hidden (pass --dont-hide-self to show)".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_filter_out_itself_when_asked_to() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
+                Process::fake(std::process::id() as usize, 0.0, None).set_arguments(vec!["bar"]),
+                Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
+            ],
+            Args {
+                dont_hide_self: true,
+                ..Args::default()
+            },
+        )?;
+        set_pattern(&mut app, "bar")?;
+        app.tick();
+        assert!(render_ui(&mut app).contains("bar"));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_cpu_relative_to_the_container_quota() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 150.0, None)],
+            Args {
+                container_cpu: true,
+                ..Args::default()
+            },
+        )?;
+        app.cpu_quota_source = CpuQuotaSource::fake(Some(2.0));
+        app.tick();
+        assert!(render_ui(&mut app).contains("  75%"));
+        Ok(())
+    }
+
+    #[test]
+    fn container_cpu_without_a_quota_shows_host_cpu() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 150.0, None)],
+            Args {
+                container_cpu: true,
+                ..Args::default()
+            },
+        )?;
+        app.cpu_quota_source = CpuQuotaSource::fake(None);
+        app.tick();
+        assert!(render_ui(&mut app).contains(" 150%"));
+        Ok(())
+    }
+
+    #[test]
+    fn collapsing_idle_subtrees() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 5.0, Some(2)),
+            Process::fake(4, 0.0, Some(1)),
+            Process::fake(5, 0.5, Some(4)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('c'))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into(), 4.into()]
+        );
+        simulate_key_press(&mut app, KeyCode::Char('c'))?;
+        assert_eq!(app.forest.iter().count(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn root_only_shows_roots_with_subtree_totals() -> R<()> {
+        let app = test_app_with_args(
+            vec![
+                Process::fake(1, 1.0, None),
+                Process::fake(2, 2.0, Some(1)),
+                Process::fake(3, 3.0, Some(2)),
+                Process::fake(4, 4.0, None),
+                Process::fake(5, 5.0, Some(4)),
+            ],
+            Args {
+                root_only: true,
+                ..Args::default()
+            },
+        )?;
+        assert_eq!(
+            app.forest
+                .iter()
+                .map(|p| (p.id(), p.cpu()))
+                .collect::<Vec<_>>(),
+            vec![(1.into(), 6.0), (4.into(), 9.0)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn setting_a_cpu_threshold_interactively() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 20.0, Some(1)),
+            Process::fake(3, 2.0, None),
+            Process::fake(4, 10.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('m'))?;
+        simulate_key_press(&mut app, KeyCode::Char('5'))?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.min_cpu, Some(5.0));
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 4.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn huge_ram_thresholds_do_not_overflow() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None).set_ram(u64::MAX),
+            Process::fake(2, 1.0, None),
+        ])?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('M'))?;
+        for key in u64::MAX.to_string().chars() {
+            simulate_key_press(&mut app, KeyCode::Char(key))?;
+        }
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.min_ram_mb, Some(u64::MAX));
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clearing_a_threshold() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 1.0, None)])?;
+        app.min_cpu = Some(5.0);
+        simulate_key_press(&mut app, KeyCode::Char('m'))?;
+        simulate_key_press(&mut app, KeyCode::Backspace)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.min_cpu, None);
+        Ok(())
+    }
+
+    #[test]
+    fn every_refresh_appends_a_log_line() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 1.0, None),
+            Process::fake(2, 2.0, Some(1)),
+            Process::fake(3, 4.0, None),
+        ])?;
+        let buffer = SharedBuffer::default();
+        app.usage_log = Some(UsageLog::fake(buffer.clone()));
+        app.tick();
+        // filtering doesn't affect the logged totals
+        set_pattern(&mut app, "three")?;
+        app.tick();
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.ends_with(",7.0,0")));
+        Ok(())
+    }
+
+    #[test]
+    fn printing_does_not_open_the_log() -> R<()> {
+        let log = std::env::temp_dir().join(format!("treetop-test-{}.csv", process::id()));
+        let app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)],
+            Args::parse_from(["treetop", "--log", &log.to_string_lossy()]),
+        )?;
+        app.print()?;
+        assert!(!log.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn log_write_errors_are_shown() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        app.usage_log = Some(UsageLog::fake(FailingWriter));
+        app.tick();
+        assert_eq!(
+            app.error_state,
+            Some("cannot write log: disk full".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn outline_mode_shows_descendant_counts() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, Some(2)),
+            Process::fake(4, 0.0, Some(2)),
+            Process::fake(5, 0.0, Some(1)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('o'))?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Char('e'))?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    mod details {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 10.0, None)
+                    .set_arguments(vec!["/usr/bin/python3", "-m", "http.server", "8080"])
+                    .set_cwd("/srv/www")
+                    .set_user("www-data")
+                    .set_uptime(3720)
+                    .set_ram(30 * 2_u64.pow(20)),
+                Process::fake(2, 20.0, Some(1)).set_ram(20 * 2_u64.pow(20)),
+            ]
+        }
+
+        #[test]
+        fn shows_details_of_the_selected_process() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--columns", "pid,cpu"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+            assert_snapshot!(render_ui(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn are_hidden_without_a_selected_process() -> R<()> {
+            let mut app = test_app(processes())?;
+            assert!(app.selected_details().is_empty());
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            assert_eq!(app.selected_details().len(), 3);
+            simulate_key_press(&mut app, KeyCode::Esc)?;
+            assert!(app.selected_details().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn are_left_out_in_small_terminals() -> R<()> {
+            let mut app = test_app(processes())?;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            let area = Rect::new(0, 0, 80, 8);
+            let mut buffer = Buffer::filled(area, Cell::new(" "));
+            app.render(area, &mut buffer);
+            assert_eq!(app.list_area.height, 4);
+            Ok(())
+        }
+
+        #[test]
+        fn wrap_long_commands() -> R<()> {
+            let mut app = test_app_with_args(
+                vec![Process::fake(1, 10.0, None).set_arguments(vec![
+                    "/usr/bin/python3",
+                    "-m",
+                    "http.server",
+                    "--bind",
+                    "127.0.0.1",
+                    "--directory",
+                    "/srv/www/some/deeply/nested/directory",
+                    "8080",
+                ])],
+                Args::parse_from(["treetop", "--columns", "pid,cpu"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            assert_snapshot!(render_ui_in(
+                &mut app,
+                Rect::new(0, 0, 80, 12),
+                Modifier::REVERSED
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn tiny_terminals_do_not_panic() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            for error_state in [None, Some("error".to_string())] {
+                app.error_state = error_state;
+                for height in 1..=4 {
+                    render_ui_in(&mut app, Rect::new(0, 0, 80, height), Modifier::REVERSED);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn the_pid_column_grows_with_large_pids() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(123_456_789, 0.0, Some(1)),
+            ],
+            Args::parse_from(["treetop", "--columns", "pid,cpu"]),
+        )?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_the_pattern_and_the_secondary_pattern() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None).set_arguments(vec!["worker", "error", "retry"])],
+            Args {
+                highlight: Some("retry".to_string()),
+                ..Args::default()
+            },
+        )?;
+        set_pattern(&mut app, "error")?;
+        app.tick();
+        let area = Rect::new(0, 0, 120, 10);
+        let mut buffer = Buffer::filled(area, Cell::new(" "));
+        app.render(area, &mut buffer);
+        let symbols: Vec<&str> = (0..area.width).map(|x| buffer[(x, 3)].symbol()).collect();
+        let find = |word: &str| -> R<u16> {
+            let x = symbols
+                .windows(word.len())
+                .position(|window| window.concat() == word)
+                .ok_or("not found")?;
+            Ok(u16::try_from(x)?)
+        };
+        let error_x = find("error")?;
+        let retry_x = find("retry")?;
+        assert_eq!(buffer[(error_x, 3)].fg, Color::Yellow);
+        assert_eq!(buffer[(retry_x, 3)].fg, Color::Magenta);
+        assert_eq!(buffer[(error_x - 2, 3)].fg, Color::Reset);
+        Ok(())
+    }
+
+    mod themes {
+        use super::*;
+
+        fn highlighting_app(theme: &str) -> R<TreetopApp> {
+            let mut app = test_app_with_args(
+                vec![Process::fake(1, 80.0, None).set_arguments(vec!["worker", "error", "retry"])],
+                Args::parse_from([
+                    "treetop",
+                    "--columns",
+                    "pid,cpu",
+                    "--highlight",
+                    "retry",
+                    "--theme",
+                    theme,
+                ]),
+            )?;
+            set_pattern(&mut app, "error")?;
+            app.tick();
+            Ok(app)
+        }
+
+        #[test]
+        fn mono_underlines_matches_instead_of_coloring_them() -> R<()> {
+            let mut app = highlighting_app("mono")?;
+            assert_snapshot!(render_ui_underlining(&mut app, Modifier::UNDERLINED));
+            let area = Rect::new(0, 0, 80, 10);
+            let mut buffer = Buffer::filled(area, Cell::new(" "));
+            app.render(area, &mut buffer);
+            for y in 0..area.height - 1 {
+                for x in 0..area.width {
+                    assert_eq!(buffer[(x, y)].fg, Color::Reset);
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn solarized_uses_its_own_palette() -> R<()> {
+            let mut app = highlighting_app("solarized")?;
+            let area = Rect::new(0, 0, 80, 10);
+            let mut buffer = Buffer::filled(area, Cell::new(" "));
+            app.render(area, &mut buffer);
+            let colors: Vec<Color> = (0..area.width).map(|x| buffer[(x, 3)].fg).collect();
+            assert!(colors.contains(&Color::Rgb(0xb5, 0x89, 0x00)));
+            assert!(colors.contains(&Color::Rgb(0xd3, 0x36, 0x82)));
+            assert!(colors.contains(&Color::Rgb(0xdc, 0x32, 0x2f)));
+            assert!(!colors.contains(&Color::Yellow));
+            assert!(!colors.contains(&Color::Red));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn highlight_only_mode_keeps_non_matching_processes() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None).set_name("sshd"),
+                Process::fake(2, 0.0, None).set_name("cron"),
+                Process::fake(3, 0.0, None).set_name("ssh-agent"),
+            ],
+            Args::parse_from(["treetop", "--columns", "pid"]),
+        )?;
+        set_pattern(&mut app, "ssh")?;
+        app.tick();
+        assert_eq!(app.forest.iter().count(), 2);
+        simulate_key_press(&mut app, KeyCode::Char('h'))?;
+        assert_eq!(app.forest.iter().count(), 3);
+        assert!(app
+            .status_notes()
+            .contains(&"search pattern (highlight only): ssh".to_string()));
+        assert_snapshot!(render_ui_underlining(&mut app, Modifier::BOLD));
+        simulate_key_press(&mut app, KeyCode::Char('h'))?;
+        assert_eq!(app.forest.iter().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn shows_full_executable_paths() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None)
+                .set_arguments(vec!["foo", "--bar"])
+                .set_exe("/usr/bin/foo"),
+            Process::fake(2, 0.0, None)
+                .set_arguments(vec!["./foo"])
+                .set_exe("/opt/foo"),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('p'))?;
+        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('p'))?;
+        assert_eq!(
+            app.forest
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["foo --bar", "foo"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn full_paths_can_be_enabled_on_the_command_line() -> R<()> {
+        let app = test_app_with_args(
+            vec![Process::fake(1, 0.0, None)
+                .set_arguments(vec!["foo"])
+                .set_exe("/opt/foo")],
+            Args::parse_from(["treetop", "--full-path"]),
+        )?;
+        assert_eq!(
+            app.forest
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["/opt/foo"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn truncates_long_command_lines_with_an_ellipsis() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)).set_arguments(vec![
+                "java",
+                "-Xmx4g",
+                "-classpath",
+                "/opt/app/lib/a.jar:/opt/app/lib/b.jar",
+                "com.example.Main",
+            ]),
+        ])?;
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    mod horizontal_scrolling {
+        use super::*;
+
+        fn long_command() -> Process {
+            Process::fake(2, 0.0, None).set_arguments(vec![
+                "java",
+                "-Xmx4g",
+                "-classpath",
+                "/opt/app/lib/a.jar:/opt/app/lib/b.jar",
+                "com.example.Main",
+            ])
+        }
+
+        #[test]
+        fn scrolls_the_command_of_the_selected_row() -> R<()> {
+            let mut app = test_app(vec![long_command(), Process::fake(3, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Right)?;
+            simulate_key_press(&mut app, KeyCode::Right)?;
+            assert_snapshot!(render_ui(&mut app));
+            simulate_key_press(&mut app, KeyCode::Left)?;
+            assert_eq!(app.horizontal_scroll, Some((2.into(), 8)));
+            Ok(())
+        }
+
+        #[test]
+        fn resets_when_the_selection_changes() -> R<()> {
+            let mut app = test_app(vec![long_command(), Process::fake(3, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Right)?;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            assert_eq!(app.horizontal_scroll, None);
+            simulate_key_press(&mut app, KeyCode::Up)?;
+            assert_eq!(app.horizontal_offset(2.into()), 0);
+            Ok(())
+        }
+
+        #[test]
+        fn stops_before_the_end_of_the_command() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None).set_name("short")])?;
+            simulate_key_press(&mut app, KeyCode::Right)?;
+            simulate_key_press(&mut app, KeyCode::Right)?;
+            assert_eq!(app.horizontal_scroll, Some((1.into(), 4)));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shows_invalid_patterns_in_red() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        for char in "tw(".chars() {
+            simulate_key_press(&mut app, KeyCode::Char(char))?;
+        }
+        app.debounced_update();
+        let area = Rect::new(0, 0, 200, 10);
+        let mut buffer = Buffer::filled(area, Cell::new(" "));
+        app.render(area, &mut buffer);
+        let status_bar: String = (0..area.width)
+            .map(|x| buffer[(x, area.height - 1)].symbol())
+            .collect();
+        assert_snapshot!(status_bar.trim_end());
+        let input_start = status_bar.find("type").ok_or("no input in status bar")?;
+        let input_start = status_bar[..input_start].chars().count();
+        assert_eq!(
+            buffer[(u16::try_from(input_start)?, area.height - 1)].fg,
+            Color::Red
+        );
+        assert_eq!(buffer[(0, area.height - 1)].fg, Color::Yellow);
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_patterns_are_shown_before_they_are_applied() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Char('('))?;
+        assert!(!app.pattern.is_invalid());
+        assert!(app.status_bar_text().ends_with("(▌ (invalid regex)"));
+        simulate_key_press(&mut app, KeyCode::Char(')'))?;
+        assert!(app.status_bar_text().ends_with("()▌"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_terms_do_not_stop_the_other_terms_from_filtering() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_name("init"),
+            Process::fake(2, 0.0, Some(1)).set_name("nginx"),
+            Process::fake(3, 0.0, Some(1)).set_name("cron"),
+        ])?;
+        set_pattern(&mut app, "nginx,(")?;
+        app.tick();
+        assert!(app.pattern.is_invalid());
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into()]
+        );
+        Ok(())
+    }
+
+    mod narrow_status_bar {
+        use super::*;
+
+        fn render_status_bar(app: &mut TreetopApp) -> String {
+            let area = Rect::new(0, 0, 30, 10);
+            let mut buffer = Buffer::filled(area, Cell::new(" "));
+            app.render(area, &mut buffer);
+            (0..area.width)
+                .map(|x| buffer[(x, area.height - 1)].symbol())
+                .collect()
+        }
+
+        #[test]
+        fn drops_hints_that_dont_fit() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            assert_snapshot!(render_status_bar(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn keeps_the_search_pattern() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Char('/'))?;
+            for char in "foo".chars() {
+                simulate_key_press(&mut app, KeyCode::Char(char))?;
+            }
+            assert_snapshot!(render_status_bar(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn shows_the_end_of_inputs_that_dont_fit_on_their_own() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Char('/'))?;
+            for char in "a-very-long-search-pattern".chars() {
+                simulate_key_press(&mut app, KeyCode::Char(char))?;
+            }
+            assert_snapshot!(render_status_bar(&mut app));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shows_a_message_when_nothing_matches() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_name("bash"),
+            Process::fake(2, 0.0, Some(1)).set_name("sleep"),
+        ])?;
+        set_pattern(&mut app, "nothing-matches-this")?;
+        app.tick();
+        assert_snapshot!(render_ui(&mut app));
+        Ok(())
+    }
+
+    #[test]
+    fn shows_a_message_when_there_are_no_processes() -> R<()> {
+        let mut app = test_app(vec![])?;
+        let ui = render_ui(&mut app);
+        assert!(ui.contains("No processes"), "{ui}");
+        Ok(())
+    }
+
+    #[test]
+    fn patterns_can_be_restricted_to_whole_words() -> R<()> {
+        let processes = || {
+            vec![
+                Process::fake(1, 0.0, None).set_name("assist"),
+                Process::fake(2, 0.0, None).set_name("ss"),
+            ]
+        };
+        let app = test_app_with_args(processes(), Args::parse_from(["treetop", "--word", "ss"]))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "ss"]))?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::CONTROL, KeyCode::Char('w'))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        assert!(app
+            .status_bar_text()
+            .ends_with("type search pattern (whole words): ss▌"));
+        Ok(())
+    }
+
+    #[test]
+    fn fuzzy_matching() -> R<()> {
+        let processes = || {
+            vec![
+                Process::fake(1, 0.0, None).set_name("firefox"),
+                Process::fake(2, 0.0, None).set_name("fixer"),
+            ]
+        };
+        let app = test_app_with_args(
+            processes(),
+            Args::parse_from(["treetop", "--fuzzy", "frfx"]),
+        )?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "frfx"]))?;
+        assert!(app.forest.iter().next().is_none());
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::CONTROL, KeyCode::Char('z'))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        assert!(app.status_bar_text().ends_with("type search fuzzy: frfx▌"));
+        Ok(())
+    }
+
+    mod threads {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None).set_name("server"),
+                Process::fake(2, 1.0, Some(1))
+                    .set_name("worker")
+                    .set_thread(),
+                Process::fake(3, 2.0, Some(1)).set_name("io").set_thread(),
+                Process::fake(4, 0.0, Some(1)).set_name("child"),
+            ]
+        }
+
+        #[test]
+        fn threads_are_hidden_by_default() -> R<()> {
+            let app = test_app(processes())?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![1.into(), 4.into()]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn shows_threads_below_their_process() -> R<()> {
+            let mut app =
+                test_app_with_args(processes(), Args::parse_from(["treetop", "--threads"]))?;
+            assert_snapshot!(render_ui(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn threads_can_be_toggled_at_runtime() -> R<()> {
+            let mut app = test_app(processes())?;
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('H'))?;
+            assert_eq!(app.forest.iter().count(), 4);
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('H'))?;
+            assert_eq!(app.forest.iter().count(), 2);
+            Ok(())
+        }
+
+        #[test]
+        fn signalling_the_subtree_of_a_thread() -> R<()> {
+            let signaler = FakeSignaler::fake();
+            let mut app = test_app_with_signaler(
+                processes(),
+                signaler.clone(),
+                Args::parse_from(["treetop", "--threads", "--select", "2"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Char('x'))?;
+            assert_eq!(
+                app.status_bar_text(),
+                "Send SIGTERM to worker (2) and its 0 descendants? [y/N]"
+            );
+            simulate_key_press(&mut app, KeyCode::Char('y'))?;
+            assert_eq!(
+                *signaler.sent.borrow(),
+                vec![(nix::unistd::Pid::from_raw(2), Signal::SIGTERM)]
+            );
+            Ok(())
+        }
+    }
+
+    mod kernel_group {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(2, 0.0, None).set_kernel_thread(),
+                Process::fake(3, 0.0, Some(2)).set_kernel_thread(),
+                Process::fake(4, 0.0, Some(2)).set_kernel_thread(),
+                Process::fake(5, 0.0, Some(1)),
+            ]
+        }
+
+        #[test]
+        fn groups_kernel_threads_below_a_synthetic_root() -> R<()> {
+            let mut app =
+                test_app_with_args(processes(), Args::parse_from(["treetop", "--group-kernel"]))?;
+            assert_snapshot!(render_ui(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn the_synthetic_root_cannot_be_selected() -> R<()> {
+            let mut app =
+                test_app_with_args(processes(), Args::parse_from(["treetop", "--group-kernel"]))?;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            set_pattern(&mut app, "kernel")?;
+            app.update_processes();
+            assert_eq!(app.matching_pids(), vec![]);
+            Ok(())
+        }
+
+        #[test]
+        fn the_synthetic_root_cannot_be_selected_on_the_command_line() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--group-kernel", "--select", "0"]),
+            )?;
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            assert_eq!(app.selected_pid(), Some(0.into()));
+            simulate_key_press(&mut app, KeyCode::Char('t'))?;
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            assert!(app.history.back().is_none());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shows_matches_as_a_flat_list() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(2, 0.0, Some(1)),
+                Process::fake(3, 0.0, Some(2)),
+                Process::fake(4, 0.0, Some(3)),
+                Process::fake(5, 0.0, Some(1)),
+            ],
+            Args::parse_from(["treetop", "--flat", "four"]),
+        )?;
+        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('f'))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into(), 4.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn flat_lists_are_sorted_as_a_whole() -> R<()> {
+        let mut app = test_app_with_args(
+            vec![
+                Process::fake(1, 3.0, None),
+                Process::fake(2, 1.0, Some(1)),
+                Process::fake(3, 2.0, None),
+            ],
+            Args::parse_from(["treetop", "--flat", "--no-aggregate", "--sort", "cpu", "."]),
+        )?;
+        app.update_processes();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 3.into(), 2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filtering_by_pid_range_keeps_ancestors() -> R<()> {
+        let app = test_app_with_args(
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(10, 0.0, Some(1)),
+                Process::fake(20, 0.0, Some(1)),
+                Process::fake(30, 0.0, Some(1)),
+                Process::fake(40, 0.0, None),
+            ],
+            Args::parse_from(["treetop", "pid:20-30"]),
+        )?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 20.into(), 30.into()]
+        );
+        Ok(())
+    }
+
+    mod filtering_by_user {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None).set_user("root"),
+                Process::fake(2, 0.0, Some(1)).set_user("root"),
+                Process::fake(3, 0.0, Some(2)).set_user("alice"),
+                Process::fake(4, 0.0, Some(3)).set_user("bob"),
+                Process::fake(5, 0.0, Some(1)).set_user("bob"),
+                Process::fake(6, 0.0, None).set_user("alice"),
+            ]
+        }
+
+        fn ids(app: &TreetopApp) -> Vec<sysinfo::Pid> {
+            app.forest.iter().map(Node::id).collect()
+        }
+
+        #[test]
+        fn keeps_ancestors_owned_by_other_users() -> R<()> {
+            let app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--user", "alice"]),
+            )?;
+            assert_eq!(ids(&app), vec![1.into(), 2.into(), 3.into(), 6.into()]);
+            Ok(())
+        }
+
+        #[test]
+        fn user_only_hides_ancestors_owned_by_other_users() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--user", "alice", "--user-only"]),
+            )?;
+            assert_eq!(ids(&app), vec![3.into(), 6.into()]);
+            assert_eq!(app.forest.depth(), 1);
+            assert_snapshot!(render_ui(&mut app));
+            Ok(())
+        }
+
+        #[test]
+        fn combines_with_the_search_pattern() -> R<()> {
+            let app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--user", "bob", "pid:4-6"]),
+            )?;
+            assert_eq!(
+                ids(&app),
+                vec![1.into(), 2.into(), 3.into(), 4.into(), 5.into()]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn user_patterns_match_user_names() -> R<()> {
+            let app = test_app_with_args(processes(), Args::parse_from(["treetop", "user:ali"]))?;
+            assert_eq!(
+                ids(&app),
+                vec![1.into(), 2.into(), 3.into(), 4.into(), 6.into()]
+            );
+            let app = test_app_with_args(processes(), Args::parse_from(["treetop", "!user:bob"]))?;
+            assert_eq!(ids(&app), vec![1.into(), 2.into(), 3.into(), 6.into()]);
+            Ok(())
+        }
+
+        #[test]
+        fn user_only_requires_user() {
+            assert!(Args::try_parse_from(["treetop", "--user-only"]).is_err());
+        }
+    }
+
+    #[test]
+    fn hides_processes_by_name_unless_they_have_visible_descendants() -> R<()> {
+        let processes = || {
+            vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(2, 0.0, Some(1)).set_name("bash"),
+                Process::fake(3, 0.0, Some(2)).set_name("vim"),
+                Process::fake(4, 0.0, Some(1)).set_name("bash"),
+                Process::fake(5, 0.0, Some(1)).set_name("cron"),
+            ]
+        };
+        let args = |extra: &[&str]| {
+            Args::parse_from(
+                ["treetop", "--hide", "bash", "--hide", "cron"]
+                    .iter()
+                    .chain(extra),
+            )
+        };
+        let app = test_app_with_args(processes(), args(&[]))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into()]
+        );
+        let app = test_app_with_args(processes(), args(&["vim"]))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into()]
+        );
+        let app = test_app_with_args(processes(), args(&["bash"]))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into(), 2.into(), 3.into()]
+        );
+        Ok(())
+    }
+
+    mod list_view {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 1.0, None).set_name("init"),
+                Process::fake(2, 5.0, Some(1)).set_name("sshd"),
+                Process::fake(3, 20.0, Some(2)).set_name("bash"),
+                Process::fake(4, 10.0, Some(3)).set_name("vim"),
+                Process::fake(5, 2.0, Some(1)).set_name("cron"),
+            ]
+        }
+
+        #[test]
+        fn lists_all_processes_sorted_by_their_own_usage() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--columns", "pid,cpu", "--sort", "cpu"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Char('v'))?;
+            assert_snapshot!(render_ui(&mut app));
+            app.tick();
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![3.into(), 4.into(), 2.into(), 5.into(), 1.into()]
+            );
+            simulate_key_press(&mut app, KeyCode::Char('v'))?;
+            assert_eq!(app.forest.depth(), 4);
+            Ok(())
+        }
+
+        #[test]
+        fn only_lists_processes_matching_the_pattern() -> R<()> {
+            let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "vim"]))?;
+            simulate_key_press(&mut app, KeyCode::Char('v'))?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![4.into()]
+            );
+            let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "!sh"]))?;
+            simulate_key_press(&mut app, KeyCode::Char('v'))?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![1.into(), 4.into(), 5.into()]
+            );
+            Ok(())
+        }
+    }
+
+    mod only_leaves {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(2, 0.0, Some(1)).set_name("sshd"),
+                Process::fake(3, 0.0, Some(2)).set_name("bash"),
+                Process::fake(4, 0.0, Some(2)).set_name("zsh"),
+                Process::fake(5, 0.0, Some(1)).set_name("cron"),
+                Process::fake(6, 0.0, Some(5)).set_name("backup"),
+            ]
+        }
+
+        #[test]
+        fn shows_only_processes_without_children() -> R<()> {
+            let mut app = test_app_with_args(
+                processes(),
+                Args::parse_from(["treetop", "--columns", "pid"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Char('l'))?;
+            assert_snapshot!(render_ui(&mut app));
+            simulate_key_press(&mut app, KeyCode::Char('l'))?;
+            assert_eq!(app.forest.depth(), 3);
+            Ok(())
+        }
+
+        #[test]
+        fn respects_the_search_pattern() -> R<()> {
+            let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "sshd"]))?;
+            simulate_key_press(&mut app, KeyCode::Char('l'))?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![3.into(), 4.into()]
+            );
+            let mut app = test_app_with_args(processes(), Args::parse_from(["treetop", "init"]))?;
+            simulate_key_press(&mut app, KeyCode::Char('l'))?;
+            assert_eq!(
+                app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+                vec![3.into(), 4.into(), 6.into()]
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failing_to_read_processes_shows_an_error_and_keeps_the_old_ones() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        assert_eq!(app.error_state, None);
+        app.process_watcher = ProcessWatcher::fake(vec![]);
+        app.tick();
+        assert_eq!(
+            app.error_state,
+            Some("failed to read processes".to_string())
+        );
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(2, 0.0, None)]);
+        app.tick();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn f5_refreshes_immediately() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(2, 0.0, None)]);
+        simulate_key_press(&mut app, KeyCode::F(5))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn f5_refreshes_while_paused() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        simulate_key_press(&mut app, KeyCode::Char(' '))?;
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(2, 0.0, None)]);
+        simulate_key_press(&mut app, KeyCode::F(5))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ticks_are_ignored_while_paused() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        simulate_key_press(&mut app, KeyCode::Char(' '))?;
+        assert!(app.status_notes().contains(&"PAUSED".to_string()));
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(2, 0.0, None)]);
+        app.tick();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        simulate_key_press(&mut app, KeyCode::Char(' '))?;
+        app.tick();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn typing_filters_and_highlights_once_debounced() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_name("sshd"),
+            Process::fake(2, 0.0, None).set_name("ssh-agent"),
+            Process::fake(3, 0.0, None).set_name("bash"),
+        ])?;
+        let shown = |app: &TreetopApp| app.forest.iter().map(Node::id).collect::<Vec<_>>();
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Char('s'))?;
+        app.debounced_update();
+        assert_eq!(shown(&app), vec![1.into(), 2.into(), 3.into()]);
+        assert!(matches!(
+            simulate_key_press(&mut app, KeyCode::Char('s'))?,
+            UpdateResult::Debounce
+        ));
+        assert_eq!(shown(&app), vec![1.into(), 2.into(), 3.into()]);
+        assert_eq!(
+            app.status_bar_parts().1.as_deref(),
+            Some("type search pattern: ss▌")
+        );
+        app.debounced_update();
+        assert_eq!(shown(&app), vec![1.into(), 2.into()]);
+        simulate_key_press(&mut app, KeyCode::Char('h'))?;
+        simulate_key_press(&mut app, KeyCode::Char('d'))?;
+        app.debounced_update();
+        assert_eq!(shown(&app), vec![1.into()]);
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buffer = Buffer::filled(area, Cell::new(" "));
+        app.render(area, &mut buffer);
+        let highlighted: String = (0..area.width)
+            .filter(|x| buffer[(*x, 3)].fg == Color::Yellow)
+            .map(|x| buffer[(x, 3)].symbol())
+            .collect();
+        assert_eq!(highlighted, "sshd");
+        Ok(())
+    }
+
+    #[test]
+    fn typing_patterns() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Char('a'))?;
+        simulate_key_press(&mut app, KeyCode::Char('b'))?;
+        assert_eq!(app.pattern_text(), "ab");
+        simulate_key_press(&mut app, KeyCode::Backspace)?;
+        assert_eq!(app.pattern_text(), "a");
+        simulate_key_press(&mut app, KeyCode::Char('('))?;
+        simulate_key_press(&mut app, KeyCode::Char('b'))?;
+        simulate_key_press(&mut app, KeyCode::Char(')'))?;
+        assert_eq!(app.pattern_text(), "a(b)");
+        app.debounced_update();
+        assert_eq!(app.pattern.as_str(), "a(b)");
+        Ok(())
+    }
+
+    #[test]
+    fn toggling_literal_search() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_arguments(vec!["a+b"]),
+            Process::fake(2, 0.0, None).set_arguments(vec!["aab"]),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        for key in "a+b".chars() {
+            simulate_key_press(&mut app, KeyCode::Char(key))?;
+        }
+        app.debounced_update();
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![2.into()]
+        );
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::CONTROL, KeyCode::Char('f'))?;
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        assert!(app
+            .status_notes()
+            .contains(&"search literal: a+b".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn typing_slashes_into_the_pattern() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None).set_arguments(vec!["a+b/c"]),
+            Process::fake(2, 0.0, None).set_arguments(vec!["a+b"]),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::CONTROL, KeyCode::Char('f'))?;
+        for key in "a+b/c".chars() {
+            simulate_key_press(&mut app, KeyCode::Char(key))?;
+        }
+        assert_eq!(app.ui_mode, UiMode::EditingPattern);
+        app.debounced_update();
+        assert_eq!(app.pattern.as_str(), "a+b/c");
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn exit_pattern_edit_mode() -> R<()> {
+        let mut app = test_app(vec![])?;
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::Normal);
+        Ok(())
+    }
+
+    #[test]
+    fn selecting_processes() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, None),
+            Process::fake(4, 0.0, Some(3)),
+        ])?;
+        assert_eq!(app.ui_mode, UiMode::Normal);
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        simulate_key_press(&mut app, KeyCode::Esc)?;
+        assert_eq!(app.ui_mode, UiMode::Normal);
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(2.into()));
+        Ok(())
+    }
+
+    mod help {
+        use super::*;
+
+        #[test]
+        fn question_mark_opens_the_help_overlay() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('?'))?;
+            assert_eq!(app.ui_mode, UiMode::Help);
+            let area = Rect::new(0, 0, 80, 50);
+            let mut buffer = Buffer::filled(area, Cell::new(" "));
+            app.render(area, &mut buffer);
+            let lines: Vec<String> = (0..area.height)
+                .map(|y| {
+                    (0..area.width)
+                        .map(|x| buffer[(x, y)].symbol())
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect();
+            assert_snapshot!(lines.join("\n"));
+            Ok(())
+        }
+
+        #[test]
+        fn any_key_closes_the_help_overlay() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            for key in [KeyCode::Char('?'), KeyCode::Esc, KeyCode::Char('q')] {
+                simulate_key_press(&mut app, KeyCode::Char('?'))?;
+                assert_eq!(app.ui_mode, UiMode::Help);
+                assert!(matches!(
+                    simulate_key_press(&mut app, key)?,
+                    UpdateResult::Continue
+                ));
+                assert_eq!(app.ui_mode, UiMode::Normal);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn keys_pressed_to_close_the_help_overlay_have_no_other_effect() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            simulate_key_press(&mut app, KeyCode::Char('?'))?;
+            simulate_key_press(&mut app, KeyCode::Char('/'))?;
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            Ok(())
+        }
+
+        fn parse_keys(keys: &str) -> Vec<(KeyModifiers, KeyCode)> {
+            keys.split([',', ' '])
+                .filter(|token| !token.is_empty())
+                .flat_map(|token| {
+                    let (modifiers, key) = if let Some(key) = token.strip_prefix("Ctrl+") {
+                        (KeyModifiers::CONTROL, key.to_lowercase())
+                    } else if let Some(key) = token.strip_prefix("Alt+") {
+                        (KeyModifiers::ALT, key.to_string())
+                    } else {
+                        (KeyModifiers::NONE, token.to_string())
+                    };
+                    let codes = match key.as_str() {
+                        "↑↓" => vec![KeyCode::Up, KeyCode::Down],
+                        "←" => vec![KeyCode::Left],
+                        "→" => vec![KeyCode::Right],
+                        "PgUp" => vec![KeyCode::PageUp],
+                        "PgDn" => vec![KeyCode::PageDown],
+                        "Tab" => vec![KeyCode::Tab],
+                        "Enter" => vec![KeyCode::Enter],
+                        "Esc" => vec![KeyCode::Esc],
+                        "Backspace" => vec![KeyCode::Backspace],
+                        "Space" => vec![KeyCode::Char(' ')],
+                        "F5" => vec![KeyCode::F(5)],
+                        key if key.chars().count() == 1 => {
+                            vec![KeyCode::Char(key.chars().next().unwrap())]
+                        }
+                        key => panic!("unknown key in the help: {key}"),
+                    };
+                    codes.into_iter().map(move |code| match code {
+                        KeyCode::Char(char) if char.is_uppercase() => {
+                            (modifiers | KeyModifiers::SHIFT, code)
+                        }
+                        code => (modifiers, code),
+                    })
+                })
+                .collect()
+        }
+
+        #[test]
+        fn all_keys_in_the_help_are_handled() {
+            for (group, bindings) in crate::help::KEYBINDINGS {
+                let modes = match *group {
+                    "Process list" => vec![UiMode::Normal],
+                    "Search pattern" => vec![UiMode::EditingPattern],
+                    "Selected process" => vec![UiMode::ProcessSelected(1.into())],
+                    "Prompts" => vec![
+                        UiMode::EditingPid,
+                        UiMode::ConfirmingSignal {
+                            pid: 1.into(),
+                            signal: nix::sys::signal::Signal::SIGTERM,
+                            descendants: None,
+                        },
+                    ],
+                    group => panic!("unknown group in the help: {group}"),
+                };
+                for (keys, _) in *bindings {
+                    for (modifiers, code) in parse_keys(keys) {
+                        let handled = modes.iter().any(|mode| {
+                            let mut app = test_app(vec![Process::fake(1, 0.0, None)]).unwrap();
+                            app.ui_mode = *mode;
+                            !matches!(
+                                simulate_key_press_with_modifiers(&mut app, modifiers, code),
+                                Ok(UpdateResult::Ignored)
+                            )
+                        });
+                        assert!(handled, "{modifiers:?} {code:?} isn't handled in {group}");
+                    }
+                }
+            }
+        }
+    }
+
+    mod esc {
+        use super::*;
+
+        #[test]
+        fn does_not_quit_from_normal_mode_by_default() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+            assert!(matches!(
+                simulate_key_press(&mut app, KeyCode::Esc)?,
+                UpdateResult::Ignored
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn quits_from_normal_mode_with_esc_quits() -> R<()> {
+            let mut app = test_app_with_args(
+                vec![Process::fake(1, 0.0, None)],
+                Args::parse_from(["treetop", "--esc-quits"]),
+            )?;
+            assert!(matches!(
+                simulate_key_press(&mut app, KeyCode::Esc)?,
+                UpdateResult::Exit
+            ));
+            Ok(())
+        }
+
+        #[test]
+        fn still_cancels_other_modes_with_esc_quits() -> R<()> {
+            let mut app = test_app_with_args(
+                vec![Process::fake(1, 0.0, None)],
+                Args::parse_from(["treetop", "--esc-quits"]),
+            )?;
+            simulate_key_press(&mut app, KeyCode::Char('/'))?;
+            assert!(matches!(
+                simulate_key_press(&mut app, KeyCode::Esc)?,
+                UpdateResult::Continue
+            ));
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+            assert!(matches!(
+                simulate_key_press(&mut app, KeyCode::Esc)?,
+                UpdateResult::Continue
+            ));
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            Ok(())
+        }
+    }
+
+    mod copying {
+        use super::*;
+        use crate::clipboard::Clipboard;
+
+        #[test]
+        fn copies_the_command_of_the_selected_process() -> R<()> {
+            let mut app = test_app(vec![
+                Process::fake(1, 0.0, None).set_name("bash"),
+                Process::fake(2, 0.0, Some(1))
+                    .set_name("sleep")
+                    .set_arguments(vec!["sleep", "10"]),
+            ])?;
+            let (clipboard, contents) = Clipboard::fake();
+            app.clipboard = clipboard;
+            simulate_key_press(&mut app, KeyCode::Down)?;
+            simulate_key_press(&mut app, KeyCode::Char('y'))?;
+            assert_eq!(contents.borrow().as_deref(), Some("sleep 10"));
+            Ok(())
+        }
+
+        #[test]
+        fn copies_in_process_selected_mode() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None).set_name("bash")])?;
+            let (clipboard, contents) = Clipboard::fake();
+            app.clipboard = clipboard;
+            simulate_key_press(&mut app, KeyCode::Enter)?;
+            simulate_key_press(&mut app, KeyCode::Char('y'))?;
+            assert_eq!(contents.borrow().as_deref(), Some("bash"));
+            assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+            Ok(())
+        }
+
+        #[test]
+        fn leaves_out_the_defunct_label_of_zombies() -> R<()> {
+            let mut app = test_app(vec![Process::fake(1, 0.0, None)
+                .set_arguments(vec!["worker", "--once"])
+                .set_status(sysinfo::ProcessStatus::Zombie)])?;
+            let (clipboard, contents) = Clipboard::fake();
+            app.clipboard = clipboard;
+            simulate_key_press(&mut app, KeyCode::Char('y'))?;
+            assert_eq!(contents.borrow().as_deref(), Some("worker --once"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn sending_sigterm_to_the_selected_process() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, None)],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('t'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(2), Signal::SIGTERM)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn offers_sigkill_when_a_terminated_process_keeps_running() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, None)],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('t'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        for _ in 0..Termination::TICKS_TO_WAIT {
+            app.tick();
+        }
+        assert!(app
+            .status_notes()
+            .contains(&"2 still running — press K to SIGKILL".to_string()));
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        assert_eq!(
+            app.ui_mode,
+            UiMode::ConfirmingSignal {
+                pid: 2.into(),
+                signal: Signal::SIGKILL,
+                descendants: None,
+            }
+        );
+        assert_eq!(signaler.sent.borrow().len(), 1);
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![
+                (nix::unistd::Pid::from_raw(2), Signal::SIGTERM),
+                (nix::unistd::Pid::from_raw(2), Signal::SIGKILL),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_when_a_signalled_process_exits() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('t'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert!(app
+            .status_notes()
+            .contains(&"waiting for 2 to exit".to_string()));
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(1, 0.0, None)]);
+        app.tick();
+        assert!(app.status_notes().contains(&"2 terminated".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn suggests_sigkill_when_a_signalled_process_persists() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('i'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        for _ in 0..Termination::TICKS_TO_WAIT - 1 {
+            app.tick();
+            assert!(app
+                .status_notes()
+                .contains(&"waiting for 2 to exit".to_string()));
+        }
+        app.tick();
+        assert!(app
+            .status_notes()
+            .contains(&"2 still running — press K to SIGKILL".to_string()));
+        Ok(())
+    }
+
+    fn still_running_test_app(signaler: FakeSignaler) -> R<TreetopApp> {
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, None)],
+            signaler,
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('t'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        for _ in 0..Termination::TICKS_TO_WAIT {
+            app.tick();
+        }
+        assert_eq!(app.termination, Some((2.into(), Termination::StillRunning)));
+        Ok(app)
+    }
+
+    #[test]
+    fn reports_when_a_process_that_was_still_running_exits() -> R<()> {
+        let mut app = still_running_test_app(FakeSignaler::fake())?;
+        app.process_watcher = ProcessWatcher::fake(vec![Process::fake(1, 0.0, None)]);
+        app.tick();
+        assert_eq!(app.termination, Some((2.into(), Termination::Terminated)));
+        Ok(())
+    }
+
+    #[test]
+    fn forgets_about_still_running_processes_on_esc() -> R<()> {
+        let mut app = still_running_test_app(FakeSignaler::fake())?;
+        simulate_key_press(&mut app, KeyCode::Esc)?;
+        assert_eq!(app.termination, None);
+        Ok(())
+    }
+
+    #[test]
+    fn forgets_about_still_running_processes_when_selecting_another_process() -> R<()> {
+        let mut app = still_running_test_app(FakeSignaler::fake())?;
+        simulate_key_press(&mut app, KeyCode::Up)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        assert_eq!(app.termination, None);
+        assert!(!app
+            .status_notes()
+            .iter()
+            .any(|note| note.contains("still running")));
+        Ok(())
+    }
+
+    #[test]
+    fn sigkill_goes_to_the_selected_process_after_another_one_kept_running() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = still_running_test_app(signaler.clone())?;
+        simulate_key_press(&mut app, KeyCode::Up)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        assert_eq!(
+            app.ui_mode,
+            UiMode::ConfirmingSignal {
+                pid: 1.into(),
+                signal: Signal::SIGKILL,
+                descendants: None,
+            }
+        );
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![
+                (nix::unistd::Pid::from_raw(2), Signal::SIGTERM),
+                (nix::unistd::Pid::from_raw(1), Signal::SIGKILL),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn does_not_wait_for_processes_to_exit_after_sigstop() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None)])?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('s'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(app.termination, None);
+        Ok(())
+    }
+
+    #[test]
+    fn signals_are_sent_after_confirming() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None)],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        assert_eq!(
+            app.ui_mode,
+            UiMode::ConfirmingSignal {
+                pid: 1.into(),
+                signal: Signal::SIGKILL,
+                descendants: None,
+            }
+        );
+        assert!(signaler.sent.borrow().is_empty());
+        assert_eq!(app.status_bar_text(), "Send SIGKILL to one (1)? [y/N]");
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(1), Signal::SIGKILL)]
+        );
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        Ok(())
+    }
+
+    #[test]
+    fn signals_are_cancelled_by_any_other_key() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None)],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        simulate_key_press(&mut app, KeyCode::Char('n'))?;
+        assert!(signaler.sent.borrow().is_empty());
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        Ok(())
+    }
+
+    #[test]
+    fn no_confirm_sends_signals_immediately() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None)],
+            signaler.clone(),
+            Args::parse_from(["treetop", "--no-confirm"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(1), Signal::SIGKILL)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn signalling_a_whole_subtree() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![
+                Process::fake(1, 0.0, None),
+                Process::fake(2, 0.0, Some(1)),
+                Process::fake(3, 0.0, Some(2)),
+                Process::fake(4, 0.0, Some(2)),
+                Process::fake(5, 0.0, Some(1)),
+            ],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('x'))?;
+        assert_eq!(
+            app.status_bar_text(),
+            "Send SIGTERM to two (2) and its 2 descendants? [y/N]"
+        );
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![
+                (nix::unistd::Pid::from_raw(2), Signal::SIGTERM),
+                (nix::unistd::Pid::from_raw(3), Signal::SIGTERM),
+                (nix::unistd::Pid::from_raw(4), Signal::SIGTERM),
+            ]
+        );
+        Ok(())
+    }
+
+    mod signalling_matches {
+        use super::*;
+
+        fn processes() -> Vec<Process> {
+            vec![
+                Process::fake(1, 0.0, None).set_name("init"),
+                Process::fake(2, 0.0, Some(1)).set_name("worker"),
+                Process::fake(3, 0.0, Some(2)).set_name("helper"),
+                Process::fake(4, 0.0, Some(1)).set_name("worker"),
+                Process::fake(5, 0.0, None).set_name("shell"),
+            ]
+        }
+
+        #[test]
+        fn targets_only_processes_that_match_themselves() -> R<()> {
+            let mut app = test_app(processes())?;
+            set_pattern(&mut app, "worker")?;
+            app.update_processes();
+            assert_eq!(app.matching_pids(), vec![2.into(), 4.into()]);
+            Ok(())
+        }
+
+        #[test]
+        fn targets_nothing_without_a_pattern() -> R<()> {
+            let mut app = test_app(processes())?;
+            assert_eq!(app.matching_pids(), vec![]);
+            set_pattern(&mut app, "!worker")?;
+            app.update_processes();
+            assert_eq!(app.matching_pids(), vec![]);
+            Ok(())
+        }
+
+        #[test]
+        fn asks_for_confirmation() -> R<()> {
+            let signaler = FakeSignaler::fake();
+            let mut app = test_app_with_signaler(processes(), signaler.clone(), Args::default())?;
+            set_pattern(&mut app, "worker")?;
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('T'))?;
+            assert_eq!(
+                app.status_bar_text(),
+                "Send SIGTERM to all 2 processes matching 'worker'? [y/N]"
+            );
+            assert!(signaler.sent.borrow().is_empty());
+            simulate_key_press(&mut app, KeyCode::Char('y'))?;
+            assert_eq!(
+                *signaler.sent.borrow(),
+                vec![
+                    (nix::unistd::Pid::from_raw(2), Signal::SIGTERM),
+                    (nix::unistd::Pid::from_raw(4), Signal::SIGTERM),
+                ]
+            );
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            Ok(())
+        }
 
-    const RECT: Rect = Rect {
-        x: 0,
-        y: 0,
-        width: 80,
-        height: 20,
-    };
+        #[test]
+        fn refuses_to_signal_everything() -> R<()> {
+            let signaler = FakeSignaler::fake();
+            let mut app = test_app_with_signaler(processes(), signaler.clone(), Args::default())?;
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('T'))?;
+            assert_eq!(app.ui_mode, UiMode::Normal);
+            assert_eq!(
+                app.error_state,
+                Some("no processes match the search pattern".to_string())
+            );
+            assert!(signaler.sent.borrow().is_empty());
+            Ok(())
+        }
 
-    #[test]
-    fn normalize_leaves_state_unmodified() {
-        let mut list_state = ListState::default().with_selected(Some(7)).with_offset(5);
-        normalize_list_state(&mut list_state, &[(); 30], RECT);
-        assert_eq!(list_state.selected(), Some(7));
-        assert_eq!(list_state.offset(), 5);
+        #[test]
+        fn summarizes_failures() -> R<()> {
+            let mut app = test_app_with_signaler(
+                processes(),
+                FakeSignaler::failing_with(Errno::EPERM),
+                Args::parse_from(["treetop", "--no-confirm", "worker"]),
+            )?;
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('T'))?;
+            assert_eq!(
+                app.error_state,
+                Some("failed to signal 2 of 2 processes: 2 (EPERM), 4 (EPERM)".to_string())
+            );
+            Ok(())
+        }
     }
 
     #[test]
-    fn normalize_caps_at_the_list_end() {
-        let mut list_state = ListState::default().with_selected(Some(11));
-        normalize_list_state(&mut list_state, &[(); 10], RECT);
-        assert_eq!(list_state.selected(), Some(9));
+    fn summarizes_failures_when_signalling_a_subtree() -> R<()> {
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, Some(1))],
+            FakeSignaler::failing_with(Errno::EPERM),
+            Args::parse_from(["treetop", "--no-confirm"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('X'))?;
+        assert_eq!(
+            app.error_state,
+            Some("failed to signal 2 of 2 processes: 1 (EPERM), 2 (EPERM)".to_string())
+        );
+        Ok(())
     }
 
     #[test]
-    fn normalize_resets_offset_to_zero_when_the_list_fits_the_area() {
-        let mut list_state = ListState::default().with_selected(Some(0)).with_offset(5);
-        normalize_list_state(&mut list_state, &[(); 10], RECT);
-        assert_eq!(list_state.offset(), 0);
+    fn maps_keys_to_signals() {
+        assert_eq!(signal_for_key('t'), Some(Signal::SIGTERM));
+        assert_eq!(signal_for_key('k'), None);
+        assert_eq!(signal_for_key('h'), Some(Signal::SIGHUP));
+        assert_eq!(signal_for_key('i'), Some(Signal::SIGINT));
+        assert_eq!(signal_for_key('s'), Some(Signal::SIGSTOP));
+        assert_eq!(signal_for_key('c'), Some(Signal::SIGCONT));
+        assert_eq!(signal_for_key('x'), None);
     }
 
     #[test]
-    fn normalize_scrolls_up_when_offset_is_too_big() {
-        let mut list_state = ListState::default().with_selected(Some(0)).with_offset(25);
-        normalize_list_state(&mut list_state, &[(); 30], RECT);
-        assert_eq!(list_state.offset(), 10);
-    }
-
-    fn test_app(processes: Vec<Process>) -> R<TreetopApp> {
-        test_app_with_args(processes, Args::default())
-    }
-
-    fn test_app_with_args(processes: Vec<Process>, args: Args) -> R<TreetopApp> {
-        let mut app = TreetopApp::new(ProcessWatcher::fake(processes), args)?;
-        app.tick();
-        Ok(app)
+    fn sigkill_needs_a_modifier() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None), Process::fake(2, 0.0, None)],
+            signaler.clone(),
+            Args::parse_from(["treetop", "--no-confirm"]),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('k'))?;
+        assert!(signaler.sent.borrow().is_empty());
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(2.into()));
+        assert_eq!(app.list_state.selected(), Some(0));
+        simulate_key_press(&mut app, KeyCode::Char('j'))?;
+        assert_eq!(app.list_state.selected(), Some(1));
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::CONTROL, KeyCode::Char('k'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(2), Signal::SIGKILL)]
+        );
+        signaler.sent.borrow_mut().clear();
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(2), Signal::SIGKILL)]
+        );
+        Ok(())
     }
 
-    fn render_ui(app: &mut TreetopApp) -> String {
-        let area = Rect::new(0, 0, 80, 10);
-        let mut buffer = Buffer::filled(area, Cell::new(" "));
-        app.render(area, &mut buffer);
-        let mut result = String::new();
-        for y in 0..area.height {
-            for x in 0..area.width {
-                let symbol = buffer[(x, y)].symbol();
-                let symbol = if buffer[(x, y)].modifier.contains(Modifier::REVERSED) {
-                    crate::utils::test::underline(symbol)
-                } else {
-                    symbol.to_string()
-                };
-                result.push_str(&symbol);
-            }
-            result.push('\n');
-        }
-        result
+    #[test]
+    fn sending_sigstop_to_the_selected_process() -> R<()> {
+        let signaler = FakeSignaler::fake();
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None)],
+            signaler.clone(),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Char('s'))?;
+        simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert_eq!(
+            *signaler.sent.borrow(),
+            vec![(nix::unistd::Pid::from_raw(1), Signal::SIGSTOP)]
+        );
+        Ok(())
     }
 
-    fn simulate_key_press(app: &mut TreetopApp, code: KeyCode) -> R<UpdateResult> {
-        app.update(KeyEvent {
-            code,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            state: KeyEventState::NONE,
-        })
+    #[test]
+    fn signalling_an_exited_process_shows_an_error() -> R<()> {
+        let mut app = test_app_with_signaler(
+            vec![Process::fake(1, 0.0, None)],
+            FakeSignaler::failing_with(Errno::ESRCH),
+            Args::default(),
+        )?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('K'))?;
+        let result = simulate_key_press(&mut app, KeyCode::Char('y'))?;
+        assert!(matches!(result, UpdateResult::Continue));
+        assert_eq!(
+            app.error_state,
+            Some("process no longer exists".to_string())
+        );
+        Ok(())
     }
 
-    fn set_pattern(app: &mut TreetopApp, pattern: &str) -> R<()> {
-        app.pattern = crate::regex::Regex::new(pattern)?;
+    #[test]
+    fn the_cursor_follows_the_selected_process_when_rows_move() -> R<()> {
+        let mut app = test_app(vec![
+            Process::fake(1, 3.0, None),
+            Process::fake(2, 2.0, None),
+            Process::fake(3, 1.0, None),
+        ])?;
+        simulate_key_press(&mut app, KeyCode::Tab)?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        assert_eq!(app.selected_pid(), Some(2.into()));
+        app.process_watcher = ProcessWatcher::fake(vec![
+            Process::fake(1, 3.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 1.0, None),
+        ]);
+        app.tick();
+        assert_eq!(app.list_state.selected(), Some(2));
+        assert_eq!(app.selected_pid(), Some(2.into()));
         Ok(())
     }
 
     #[test]
-    fn shows_a_tree_with_header_and_side_columns() -> R<()> {
+    fn the_cursor_keeps_its_index_when_the_selected_process_disappears() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 4.0, None),
-            Process::fake(2, 3.0, Some(1)),
-            Process::fake(3, 2.0, Some(2)),
-            Process::fake(4, 1.0, None),
-            Process::fake(5, 0.0, Some(4)),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, None),
         ])?;
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        app.process_watcher = ProcessWatcher::fake(vec![
+            Process::fake(1, 0.0, None),
+            Process::fake(3, 0.0, None),
+        ]);
+        app.tick();
+        assert_eq!(app.selected_pid(), Some(3.into()));
         Ok(())
     }
 
+    fn simulate_mouse(app: &mut TreetopApp, kind: MouseEventKind, row: u16) {
+        app.mouse(MouseEvent {
+            kind,
+            column: 10,
+            row,
+            modifiers: KeyModifiers::NONE,
+        });
+    }
+
     #[test]
-    fn processes_get_sorted_by_pid() -> R<()> {
+    fn maps_terminal_rows_to_list_indices() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 1.0, None),
-            Process::fake(2, 2.0, None),
-            Process::fake(3, 4.0, None),
-            Process::fake(4, 3.0, None),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, None),
         ])?;
-        assert_snapshot!(render_ui(&mut app));
+        render_ui(&mut app);
+        assert_eq!(app.row_at(0), None);
+        assert_eq!(app.row_at(2), None);
+        assert_eq!(app.row_at(3), Some(0));
+        assert_eq!(app.row_at(5), Some(2));
+        assert_eq!(app.row_at(6), None);
+        assert_eq!(app.row_at(9), None);
         Ok(())
     }
 
     #[test]
-    fn processes_can_be_sorted_by_cpu() -> R<()> {
+    fn clicking_and_scrolling_moves_the_selection() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 1.0, None),
-            Process::fake(2, 2.0, None),
-            Process::fake(3, 4.0, None),
-            Process::fake(4, 3.0, None),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, None),
         ])?;
-        simulate_key_press(&mut app, KeyCode::Tab)?;
-        assert_snapshot!(render_ui(&mut app));
+        render_ui(&mut app);
+        simulate_mouse(&mut app, MouseEventKind::Down(MouseButton::Left), 5);
+        assert_eq!(app.list_state.selected(), Some(2));
+        simulate_mouse(&mut app, MouseEventKind::ScrollUp, 0);
+        assert_eq!(app.list_state.selected(), Some(1));
+        simulate_mouse(&mut app, MouseEventKind::Down(MouseButton::Left), 0);
+        assert_eq!(app.list_state.selected(), Some(1));
         Ok(())
     }
 
     #[test]
-    fn more_complicated_tree() -> R<()> {
+    fn vim_keys_move_the_selection_in_normal_mode() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 1.0, None),
-            Process::fake(2, 2.0, Some(1)),
-            Process::fake(3, 3.0, Some(2)),
-            Process::fake(4, 4.0, Some(1)),
-            Process::fake(5, 5.0, Some(4)),
-            Process::fake(6, 5.0, Some(4)),
-            Process::fake(7, 5.0, Some(6)),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
+            Process::fake(3, 0.0, None),
         ])?;
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('j'))?;
+        simulate_key_press(&mut app, KeyCode::Char('j'))?;
+        assert_eq!(app.list_state.selected(), Some(2));
+        simulate_key_press(&mut app, KeyCode::Char('k'))?;
+        assert_eq!(app.list_state.selected(), Some(1));
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char('G'))?;
+        assert_eq!(app.list_state.selected(), Some(2));
+        simulate_key_press(&mut app, KeyCode::Char('g'))?;
+        assert_eq!(app.list_state.selected(), Some(0));
         Ok(())
     }
 
     #[test]
-    fn filtering() -> R<()> {
+    fn other_keys_apply_the_typed_pattern_immediately() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 1.0, None),
-            Process::fake(2, 2.0, Some(1)),
-            Process::fake(3, 3.0, Some(2)),
-            Process::fake(4, 4.0, Some(1)),
-            Process::fake(5, 5.0, Some(4)),
-            Process::fake(6, 5.0, Some(4)),
-            Process::fake(7, 5.0, Some(6)),
+            Process::fake(1, 0.0, None).set_name("foo"),
+            Process::fake(2, 0.0, None).set_name("bar"),
         ])?;
-        set_pattern(&mut app, "four")?;
-        app.tick();
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Char('f'))?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.pattern.as_str(), "f");
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
         Ok(())
     }
 
     #[test]
-    fn filtering_with_regexes() -> R<()> {
+    fn typing_uppercase_characters_makes_patterns_case_sensitive() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 0.0, None),
-            Process::fake(2, 0.0, Some(1)),
-            Process::fake(3, 0.0, Some(1)),
-            Process::fake(4, 0.0, Some(1)),
+            Process::fake(1, 0.0, None).set_name("SSH"),
+            Process::fake(2, 0.0, None).set_name("ssh"),
         ])?;
-        set_pattern(&mut app, "two|three")?;
-        app.tick();
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        for key in "SSH".chars() {
+            simulate_key_press_with_modifiers(&mut app, KeyModifiers::SHIFT, KeyCode::Char(key))?;
+        }
+        assert_eq!(app.ui_mode, UiMode::EditingPattern);
+        app.debounced_update();
+        assert_eq!(app.pattern.as_str(), "SSH");
+        assert_eq!(
+            app.forest.iter().map(Node::id).collect::<Vec<_>>(),
+            vec![1.into()]
+        );
         Ok(())
     }
 
     #[test]
-    fn filtering_by_pid() -> R<()> {
+    fn vim_keys_are_typed_while_editing_the_pattern() -> R<()> {
         let mut app = test_app(vec![
             Process::fake(1, 0.0, None),
             Process::fake(2, 0.0, None),
-            Process::fake(3, 0.0, None),
         ])?;
-        set_pattern(&mut app, "2")?;
-        app.tick();
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+        simulate_key_press(&mut app, KeyCode::Char('j'))?;
+        simulate_key_press(&mut app, KeyCode::Char('k'))?;
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.pattern_text(), "jk");
         Ok(())
     }
 
     #[test]
-    fn filtering_by_process_arguments() -> R<()> {
+    fn jumping_to_a_pid() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
-            Process::fake(2, 0.0, None).set_arguments(vec!["bar"]),
-            Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, Some(1)),
+            Process::fake(30, 0.0, None),
         ])?;
-        set_pattern(&mut app, "bar")?;
-        app.tick();
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char(':'))?;
+        assert_eq!(app.ui_mode, UiMode::EditingPid);
+        simulate_key_press(&mut app, KeyCode::Char('3'))?;
+        simulate_key_press(&mut app, KeyCode::Char('0'))?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::Normal);
+        assert_eq!(app.list_state.selected(), Some(2));
+        assert_eq!(app.error_state, None);
         Ok(())
     }
 
     #[test]
-    fn filters_out_itself_by_default() -> R<()> {
+    fn jumping_to_a_missing_pid_shows_an_error() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
-            Process::fake(std::process::id() as usize, 0.0, None).set_arguments(vec!["bar"]),
-            Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
+            Process::fake(1, 0.0, None),
+            Process::fake(2, 0.0, None),
         ])?;
-        set_pattern(&mut app, "bar")?;
-        app.tick();
-        assert_snapshot!(render_ui(&mut app));
+        simulate_key_press(&mut app, KeyCode::Char(':'))?;
+        simulate_key_press(&mut app, KeyCode::Char('7'))?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.error_state, Some("process 7 is not shown".to_string()));
         Ok(())
     }
 
     #[test]
-    fn does_not_filter_out_itself_when_asked_to() -> R<()> {
-        let mut app = test_app_with_args(
+    fn selecting_a_pid_on_the_command_line() -> R<()> {
+        let processes = || {
             vec![
-                Process::fake(1, 0.0, None).set_arguments(vec!["foo"]),
-                Process::fake(std::process::id() as usize, 0.0, None).set_arguments(vec!["bar"]),
-                Process::fake(3, 0.0, None).set_arguments(vec!["baz"]),
-            ],
-            Args {
-                dont_hide_self: true,
-                ..Args::default()
-            },
-        )?;
-        set_pattern(&mut app, "bar")?;
-        app.tick();
-        assert!(render_ui(&mut app).contains("bar"));
+                Process::fake(1, 0.0, None),
+                Process::fake(2, 0.0, Some(1)),
+                Process::fake(30, 0.0, None),
+            ]
+        };
+        let app = test_app_with_args(processes(), Args::parse_from(["treetop", "--select", "30"]))?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(30.into()));
+        assert_eq!(app.list_state.selected(), Some(2));
+        assert_eq!(app.error_state, None);
+        let app = test_app_with_args(processes(), Args::parse_from(["treetop", "--select", "7"]))?;
+        assert_eq!(app.ui_mode, UiMode::Normal);
+        assert_eq!(app.list_state.selected(), Some(0));
+        assert_eq!(app.error_state, Some("process 7 is not shown".to_string()));
         Ok(())
     }
 
     #[test]
-    fn typing_patterns() -> R<()> {
+    fn navigating_back_and_forward_through_selections() -> R<()> {
         let mut app = test_app(vec![
             Process::fake(1, 0.0, None),
             Process::fake(2, 0.0, Some(1)),
+            Process::fake(3, 0.0, None),
         ])?;
-        simulate_key_press(&mut app, KeyCode::Char('/'))?;
-        simulate_key_press(&mut app, KeyCode::Char('a'))?;
-        simulate_key_press(&mut app, KeyCode::Char('b'))?;
-        assert_eq!(app.pattern.as_str(), "ab");
-        simulate_key_press(&mut app, KeyCode::Backspace)?;
-        assert_eq!(app.pattern.as_str(), "a");
-        simulate_key_press(&mut app, KeyCode::Char('('))?;
-        simulate_key_press(&mut app, KeyCode::Char('b'))?;
-        simulate_key_press(&mut app, KeyCode::Char(')'))?;
-        assert_eq!(app.pattern.as_str(), "a(b)");
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        simulate_key_press(&mut app, KeyCode::Down)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(3.into()));
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::ALT, KeyCode::Left)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(2.into()));
+        assert_eq!(app.list_state.selected(), Some(1));
+        simulate_key_press_with_modifiers(&mut app, KeyModifiers::ALT, KeyCode::Right)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(3.into()));
+        assert_eq!(app.list_state.selected(), Some(2));
         Ok(())
     }
 
     #[test]
-    fn exit_pattern_edit_mode() -> R<()> {
-        let mut app = test_app(vec![])?;
-        simulate_key_press(&mut app, KeyCode::Char('/'))?;
+    fn indicates_when_the_selected_process_changes_its_command() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None).set_name("supervisor")])?;
         simulate_key_press(&mut app, KeyCode::Enter)?;
-        assert_eq!(app.ui_mode, UiMode::Normal);
+        app.tick();
+        assert!(!app.status_notes().contains(&"command changed".to_string()));
+        app.process_watcher =
+            ProcessWatcher::fake(vec![Process::fake(1, 0.0, None).set_name("worker")]);
+        app.tick();
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        assert!(app.status_notes().contains(&"command changed".to_string()));
         Ok(())
     }
 
     #[test]
-    fn selecting_processes() -> R<()> {
+    fn the_command_changed_note_disappears_after_a_while() -> R<()> {
+        let mut app = test_app(vec![Process::fake(1, 0.0, None).set_name("supervisor")])?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        app.process_watcher =
+            ProcessWatcher::fake(vec![Process::fake(1, 0.0, None).set_name("worker")]);
+        app.tick();
+        for _ in 0..TreetopApp::COMMAND_CHANGED_TICKS - 1 {
+            app.tick();
+            assert!(app.status_notes().contains(&"command changed".to_string()));
+        }
+        app.tick();
+        assert!(!app.status_notes().contains(&"command changed".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn the_command_changed_note_is_cleared_when_selecting_another_process() -> R<()> {
         let mut app = test_app(vec![
-            Process::fake(1, 0.0, None),
-            Process::fake(2, 0.0, Some(1)),
-            Process::fake(3, 0.0, None),
-            Process::fake(4, 0.0, Some(3)),
+            Process::fake(1, 0.0, None).set_name("supervisor"),
+            Process::fake(2, 0.0, None),
         ])?;
-        assert_eq!(app.ui_mode, UiMode::Normal);
         simulate_key_press(&mut app, KeyCode::Enter)?;
-        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
-        simulate_key_press(&mut app, KeyCode::Esc)?;
-        assert_eq!(app.ui_mode, UiMode::Normal);
+        app.process_watcher = ProcessWatcher::fake(vec![
+            Process::fake(1, 0.0, None).set_name("worker"),
+            Process::fake(2, 0.0, None),
+        ]);
+        app.tick();
+        assert!(app.status_notes().contains(&"command changed".to_string()));
         simulate_key_press(&mut app, KeyCode::Down)?;
         simulate_key_press(&mut app, KeyCode::Enter)?;
         assert_eq!(app.ui_mode, UiMode::ProcessSelected(2.into()));
+        app.tick();
+        simulate_key_press(&mut app, KeyCode::Up)?;
+        simulate_key_press(&mut app, KeyCode::Enter)?;
+        assert_eq!(app.ui_mode, UiMode::ProcessSelected(1.into()));
+        assert!(!app.status_notes().contains(&"command changed".to_string()));
         Ok(())
     }
 
@@ -1,6 +1,7 @@
-use crate::R;
+use crate::{debounce::Debouncer, R};
 use crossterm::{
-    event::{self, KeyEvent, KeyEventKind},
+    cursor::Show,
+    event::{self, DisableMouseCapture, EnableMouseCapture, KeyEvent, KeyEventKind, MouseEvent},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
@@ -8,6 +9,7 @@ use ratatui::{
     buffer::Buffer,
     layout::Rect,
     prelude::{CrosstermBackend, Terminal},
+    style::{Color, Modifier},
     widgets::StatefulWidget,
 };
 use std::{
@@ -26,83 +28,138 @@ pub(crate) trait TuiApp {
 
     fn update(&mut self, event: KeyEvent) -> R<UpdateResult>;
 
+    /// Called once no `UpdateResult::Debounce` was returned for `DEBOUNCE_DELAY`.
+    fn debounced_update(&mut self);
+
+    fn mouse(&mut self, event: MouseEvent);
+
     fn render(&mut self, area: Rect, buf: &mut Buffer);
+
+    /// Whether colors and other text styles should be removed after rendering.
+    fn no_color(&self) -> bool;
 }
 
 pub(crate) enum UpdateResult {
     Continue,
+    /// Continue, and schedule a call to `TuiApp::debounced_update`.
+    Debounce,
     Exit,
+    /// Continue; the key isn't bound to anything in the current mode.
+    Ignored,
 }
 
+/// How long to wait after the last keystroke before running expensive updates, e.g. filtering
+/// by a pattern that is still being typed.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(150);
+
 struct AppWrapper<T>(PhantomData<T>);
 
 impl<T: TuiApp> StatefulWidget for &mut AppWrapper<T> {
     type State = T;
 
     fn render(self, area: ratatui::prelude::Rect, buf: &mut ratatui::prelude::Buffer, app: &mut T) {
-        app.render(area, buf);
+        render(app, area, buf);
     }
 }
 
-pub(crate) fn run_ui<T: TuiApp>(app: T) -> R<()> {
+/// Renders the app. If it asks for no colors, all colors and modifiers are removed afterwards,
+/// keeping the text.
+pub(crate) fn render<T: TuiApp>(app: &mut T, area: Rect, buffer: &mut Buffer) {
+    app.render(area, buffer);
+    if app.no_color() {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = &mut buffer[(x, y)];
+                cell.fg = Color::Reset;
+                cell.bg = Color::Reset;
+                cell.modifier = Modifier::empty();
+            }
+        }
+    }
+}
+
+/// Returns the app after the ui was closed.
+pub(crate) fn run_ui<T: TuiApp>(app: T, tick_length: Duration, mouse_capture: bool) -> R<T> {
     let termination_signal_received = setup_signal_handlers()?;
     stdout().execute(EnterAlternateScreen)?;
+    if mouse_capture {
+        stdout().execute(EnableMouseCapture)?;
+    }
     enable_raw_mode()?;
-    std::panic::set_hook(Box::new(|panic_info| {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
         let _ = reset_terminal();
-        eprintln!("panic: {panic_info}");
+        default_hook(panic_info);
     }));
-    match main_loop(app, &termination_signal_received) {
+    match main_loop(app, tick_length, &termination_signal_received) {
         Err(err) => {
             let _ = reset_terminal();
             Err(err)
         }
-        Ok(()) => {
+        Ok(app) => {
             reset_terminal()?;
-            Ok(())
+            Ok(app)
         }
     }
 }
 
+/// Undoes the terminal setup of `run_ui`. Used both on normal exit and when panicking. All
+/// steps are run even if some fail, and the first error is returned.
 fn reset_terminal() -> R<()> {
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
+    let results = [
+        stdout().execute(DisableMouseCapture).map(|_| ()),
+        stdout().execute(LeaveAlternateScreen).map(|_| ()),
+        stdout().execute(Show).map(|_| ()),
+        disable_raw_mode(),
+    ];
+    results.into_iter().collect::<std::io::Result<()>>()?;
     Ok(())
 }
 
-fn main_loop<T: TuiApp>(mut app: T, termination_signal_received: &Arc<AtomicBool>) -> R<()> {
+fn main_loop<T: TuiApp>(
+    mut app: T,
+    tick_length: Duration,
+    termination_signal_received: &Arc<AtomicBool>,
+) -> R<T> {
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     terminal.clear()?;
-    let tick_length = Duration::from_millis(1000);
     let mut last_tick = Instant::now();
+    let mut debouncer = Debouncer::new(DEBOUNCE_DELAY);
     app.tick();
     redraw(&mut terminal, &mut app)?;
     loop {
         if termination_signal_received.load(Ordering::Relaxed) {
             break;
         }
+        let until_tick = tick_length
+            .checked_sub(last_tick.elapsed())
+            .unwrap_or_default();
         let has_event = event::poll(
-            tick_length
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_default(),
+            debouncer
+                .remaining(Instant::now())
+                .map_or(until_tick, |remaining| remaining.min(until_tick)),
         )?;
         if has_event {
-            let event = event::read()?;
-            if let event::Event::Key(key) = event {
-                if key.kind == KeyEventKind::Press {
+            match event::read()? {
+                event::Event::Key(key) if key.kind == KeyEventKind::Press => {
                     match app.update(key)? {
-                        UpdateResult::Continue => {}
+                        UpdateResult::Continue | UpdateResult::Ignored => {}
+                        UpdateResult::Debounce => debouncer.trigger(Instant::now()),
                         UpdateResult::Exit => break,
                     }
                 }
+                event::Event::Mouse(mouse) => app.mouse(mouse),
+                _ => {}
             }
-        } else {
+        } else if debouncer.take_due(Instant::now()) {
+            app.debounced_update();
+        } else if last_tick.elapsed() >= tick_length {
             app.tick();
             last_tick = Instant::now();
         }
         redraw(&mut terminal, &mut app)?;
     }
-    Ok(())
+    Ok(app)
 }
 
 fn setup_signal_handlers() -> R<Arc<AtomicBool>> {
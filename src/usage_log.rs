@@ -0,0 +1,98 @@
+use crate::R;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(crate) struct UsageLog {
+    writer: Box<dyn Write>,
+}
+
+impl fmt::Debug for UsageLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UsageLog").finish_non_exhaustive()
+    }
+}
+
+impl UsageLog {
+    pub(crate) fn open(path: &Path) -> R<UsageLog> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut writer: Box<dyn Write> = Box::new(file);
+        if path.metadata()?.len() == 0 {
+            writeln!(writer, "timestamp,cpu,ram")?;
+        }
+        Ok(UsageLog { writer })
+    }
+
+    /// Appends one csv line with the current unix timestamp, the total cpu usage (in percent)
+    /// and the total ram usage (in bytes).
+    pub(crate) fn append(&mut self, cpu: f32, ram: u64) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?
+            .as_secs();
+        writeln!(self.writer, "{timestamp},{cpu:.1},{ram}")?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    pub(crate) struct SharedBuffer(pub(crate) Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub(crate) struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedBuffer {
+        pub(crate) fn lines(&self) -> Vec<String> {
+            String::from_utf8_lossy(&self.0.borrow())
+                .lines()
+                .map(ToString::to_string)
+                .collect()
+        }
+    }
+
+    impl UsageLog {
+        pub(crate) fn fake(writer: impl Write + 'static) -> UsageLog {
+            UsageLog {
+                writer: Box::new(writer),
+            }
+        }
+    }
+
+    #[test]
+    fn appends_csv_lines() -> R<()> {
+        let buffer = SharedBuffer::default();
+        let mut usage_log = UsageLog::fake(buffer.clone());
+        usage_log.append(12.5, 1024)?;
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with(",12.5,1024"));
+        Ok(())
+    }
+}
@@ -1,3 +1,62 @@
+use ratatui::text::{Line, Span};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Truncates `s` to at most `width` terminal columns without splitting grapheme clusters (e.g.
+/// characters with combining marks). Returns the truncated string and whether anything was cut.
+pub(crate) fn truncate_to_width(s: &str, width: usize) -> (String, bool) {
+    let mut result = String::new();
+    let mut result_width = 0;
+    for grapheme in s.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if result_width + grapheme_width > width {
+            return (result, true);
+        }
+        result.push_str(grapheme);
+        result_width += grapheme_width;
+    }
+    (result, false)
+}
+
+/// Drops the first `width` terminal columns of `s`. A wide character that would be split is
+/// dropped entirely.
+pub(crate) fn skip_width(s: &str, width: usize) -> &str {
+    let mut skipped = 0;
+    for (index, grapheme) in s.grapheme_indices(true) {
+        if skipped >= width {
+            return &s[index..];
+        }
+        skipped += grapheme.width();
+    }
+    ""
+}
+
+/// Truncates `line` to at most `width` columns, replacing the last visible column with an
+/// ellipsis if anything was cut. Styles of the remaining spans are kept.
+pub(crate) fn truncate_line(line: Line<'_>, width: usize) -> Line<'_> {
+    if line.width() <= width {
+        return line;
+    }
+    let mut spans = Vec::new();
+    let mut remaining = width.saturating_sub(1);
+    let mut ellipsis_style = line.style;
+    for span in line.spans {
+        let (content, truncated) = truncate_to_width(&span.content, remaining);
+        remaining -= content.width();
+        ellipsis_style = span.style;
+        if !content.is_empty() {
+            spans.push(Span::styled(content, span.style));
+        }
+        if truncated {
+            break;
+        }
+    }
+    if width > 0 {
+        spans.push(Span::styled("…", ellipsis_style));
+    }
+    Line::from(spans).style(line.style)
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     pub(crate) fn render_number(n: usize) -> &'static str {
@@ -15,4 +74,81 @@ pub(crate) mod test {
     pub(crate) fn underline(s: &str) -> String {
         format!("{s}\u{35f}")
     }
+
+    mod skip_width {
+        use crate::utils::skip_width;
+
+        #[test]
+        fn skips_ascii() {
+            assert_eq!(skip_width("foobar", 0), "foobar");
+            assert_eq!(skip_width("foobar", 3), "bar");
+            assert_eq!(skip_width("foobar", 10), "");
+        }
+
+        #[test]
+        fn does_not_split_wide_characters() {
+            assert_eq!(skip_width("日本語", 2), "本語");
+            assert_eq!(skip_width("日本語", 3), "語");
+        }
+    }
+
+    mod truncate_line {
+        use crate::utils::truncate_line;
+        use ratatui::style::Stylize;
+        use ratatui::text::{Line, Span};
+
+        #[test]
+        fn leaves_short_lines_untouched() {
+            let line = Line::from(vec![Span::raw("foo "), "bar".red()]);
+            assert_eq!(truncate_line(line.clone(), 7), line);
+        }
+
+        #[test]
+        fn ends_truncated_lines_with_an_ellipsis() {
+            let line = Line::from(vec![Span::raw("foo "), "barbaz".red()]);
+            assert_eq!(
+                truncate_line(line, 7),
+                Line::from(vec![Span::raw("foo "), "ba".red(), "…".red()])
+            );
+        }
+
+        #[test]
+        fn drops_spans_that_do_not_fit_anymore() {
+            let line = Line::from(vec![Span::raw("foo"), "bar".red()]);
+            assert_eq!(
+                truncate_line(line, 4),
+                Line::from(vec![Span::raw("foo"), "…".red()])
+            );
+        }
+    }
+
+    mod truncate_to_width {
+        use crate::utils::truncate_to_width;
+
+        #[test]
+        fn leaves_short_strings_untouched() {
+            assert_eq!(truncate_to_width("foo", 5), ("foo".to_string(), false));
+            assert_eq!(truncate_to_width("foo", 3), ("foo".to_string(), false));
+        }
+
+        #[test]
+        fn truncates_ascii() {
+            assert_eq!(truncate_to_width("foobar", 3), ("foo".to_string(), true));
+        }
+
+        #[test]
+        fn counts_wide_characters_as_two_columns() {
+            assert_eq!(truncate_to_width("日本語", 4), ("日本".to_string(), true));
+            assert_eq!(truncate_to_width("日本語", 5), ("日本".to_string(), true));
+        }
+
+        #[test]
+        fn keeps_combining_marks_with_their_base_character() {
+            let s = "e\u{301}e\u{301}e\u{301}";
+            assert_eq!(
+                truncate_to_width(s, 2),
+                ("e\u{301}e\u{301}".to_string(), true)
+            );
+        }
+    }
 }